@@ -0,0 +1,301 @@
+//! Plays many seeded runs with a bot and reports how each microgame and timer tier holds up
+//!
+//! cargo run --bin balance -- --runs 1000 --reaction 0.3 --miss 0.1 --format json
+
+use std::{collections::BTreeMap, env, process, str::FromStr};
+
+use microship::game::{
+    bot::BotSkill,
+    run_config::GameMode,
+    sim::{simulate_run, RunReport},
+};
+
+const USAGE: &str = "usage: balance [--runs N] [--seed FIRST_SEED] [--fps N] \
+[--speed PX_PER_SEC] [--reaction SECONDS] [--miss RATE] [--format csv|json] \
+[--mode short|standard|marathon|one_life]";
+
+#[derive(PartialEq)]
+enum Format {
+    Csv,
+    Json,
+}
+
+struct Options {
+    runs: u64,
+    first_seed: u64,
+    fps: f32,
+    skill: BotSkill,
+    format: Format,
+    mode: GameMode,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            runs: 500,
+            first_seed: 0,
+            fps: 60.0,
+            skill: BotSkill {
+                cursor_speed: 1800.0,
+                reaction_delay: 0.25,
+                miss_rate: 0.05,
+            },
+            format: Format::Csv,
+            mode: GameMode::Standard,
+        }
+    }
+}
+
+fn parse<T: FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("bad value for {flag}: {value}"))
+}
+
+/// Reads the flags, without the program name in front
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut args = args.into_iter();
+    while let Some(flag) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("missing value for {flag}"))?;
+        match flag.as_str() {
+            "--runs" => options.runs = parse(&flag, &value)?,
+            "--seed" => options.first_seed = parse(&flag, &value)?,
+            "--fps" => options.fps = parse(&flag, &value)?,
+            "--speed" => options.skill.cursor_speed = parse(&flag, &value)?,
+            "--reaction" => options.skill.reaction_delay = parse(&flag, &value)?,
+            "--miss" => options.skill.miss_rate = parse(&flag, &value)?,
+            "--format" => {
+                options.format = match value.as_str() {
+                    "csv" => Format::Csv,
+                    "json" => Format::Json,
+                    _ => return Err(format!("unknown format {value}")),
+                }
+            }
+            "--mode" => {
+                options.mode =
+                    GameMode::from_code(&value).ok_or_else(|| format!("unknown mode {value}"))?
+            }
+            _ => return Err(format!("unknown flag {flag}")),
+        }
+    }
+    Ok(options)
+}
+
+/// Results for one microgame at one timer tier
+#[derive(Default)]
+struct TierStats {
+    plays: u32,
+    wins: u32,
+    win_time_total: f32,
+}
+
+impl TierStats {
+    fn win_rate(&self) -> f32 {
+        self.wins as f32 / self.plays as f32
+    }
+
+    fn avg_win_time(&self) -> Option<f32> {
+        (self.wins > 0).then(|| self.win_time_total / self.wins as f32)
+    }
+}
+
+struct Summary {
+    runs: u64,
+    runs_won: u64,
+    /// Keyed by microgame name and time limit in milliseconds so tiers sort
+    tiers: BTreeMap<(&'static str, u32), TierStats>,
+    /// Fraction of runs still going after each stage, index 0 being the start
+    survival: Vec<f32>,
+}
+
+fn summarize(reports: &[RunReport], run_length: u8) -> Summary {
+    let mut tiers: BTreeMap<(&'static str, u32), TierStats> = BTreeMap::new();
+    for result in reports.iter().flat_map(|r| &r.results) {
+        let key = (result.name, (result.time_limit * 1000.0).round() as u32);
+        let stats = tiers.entry(key).or_default();
+        stats.plays += 1;
+        if let Some(t) = result.won_at {
+            stats.wins += 1;
+            stats.win_time_total += t;
+        }
+    }
+
+    let survival = (0..=run_length as usize)
+        .map(|stage| {
+            let alive = reports
+                .iter()
+                .filter(|r| r.won || r.stages_survived() > stage)
+                .count();
+            alive as f32 / reports.len() as f32
+        })
+        .collect();
+
+    Summary {
+        runs: reports.len() as u64,
+        runs_won: reports.iter().filter(|r| r.won).count() as u64,
+        tiers,
+        survival,
+    }
+}
+
+fn print_csv(summary: &Summary) {
+    println!("microgame,time_limit,plays,wins,win_rate,avg_win_time");
+    for ((name, limit_ms), stats) in &summary.tiers {
+        println!(
+            "{name},{:.2},{},{},{:.4},{}",
+            *limit_ms as f32 / 1000.0,
+            stats.plays,
+            stats.wins,
+            stats.win_rate(),
+            stats
+                .avg_win_time()
+                .map_or(String::new(), |t| format!("{t:.3}"))
+        );
+    }
+    println!();
+    println!("stage,alive");
+    for (stage, alive) in summary.survival.iter().enumerate() {
+        println!("{stage},{alive:.4}");
+    }
+}
+
+fn print_json(summary: &Summary) {
+    let microgames: Vec<String> = summary
+        .tiers
+        .iter()
+        .map(|((name, limit_ms), stats)| {
+            format!(
+                "    {{\"microgame\": \"{name}\", \"time_limit\": {:.2}, \"plays\": {}, \
+                 \"wins\": {}, \"win_rate\": {:.4}, \"avg_win_time\": {}}}",
+                *limit_ms as f32 / 1000.0,
+                stats.plays,
+                stats.wins,
+                stats.win_rate(),
+                stats
+                    .avg_win_time()
+                    .map_or("null".to_string(), |t| format!("{t:.3}"))
+            )
+        })
+        .collect();
+    let survival: Vec<String> = summary.survival.iter().map(|a| format!("{a:.4}")).collect();
+    println!("{{");
+    println!("  \"runs\": {},", summary.runs);
+    println!("  \"runs_won\": {},", summary.runs_won);
+    println!("  \"microgames\": [\n{}\n  ],", microgames.join(",\n"));
+    println!("  \"survival\": [{}]", survival.join(", "));
+    println!("}}");
+}
+
+fn main() {
+    let options = parse_args(env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("{e}\n{USAGE}");
+        process::exit(2);
+    });
+    if options.runs == 0 || options.fps <= 0.0 {
+        eprintln!("runs and fps have to be positive\n{USAGE}");
+        process::exit(2);
+    }
+
+    let delta = 1.0 / options.fps;
+    let reports: Vec<RunReport> = (0..options.runs)
+        .map(|i| simulate_run(options.mode, options.first_seed + i, options.skill, delta))
+        .collect();
+    let summary = summarize(&reports, options.mode.config().length);
+
+    match options.format {
+        Format::Csv => print_csv(&summary),
+        Format::Json => print_json(&summary),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use microship::game::sim::MicrogameResult;
+
+    fn args(line: &str) -> Result<Options, String> {
+        parse_args(line.split_whitespace().map(String::from))
+    }
+
+    fn result(name: &'static str, time_limit: f32, won_at: Option<f32>) -> MicrogameResult {
+        MicrogameResult {
+            name,
+            boss: false,
+            stage: 0,
+            time_limit,
+            won_at,
+        }
+    }
+
+    fn report(won: bool, results: Vec<MicrogameResult>) -> RunReport {
+        RunReport {
+            seed: 0,
+            won,
+            results,
+        }
+    }
+
+    #[test]
+    fn flags_override_the_defaults() {
+        let options =
+            args("--runs 20 --seed 7 --reaction 0.5 --miss 0.2 --format json --mode short")
+                .unwrap();
+        assert_eq!(options.runs, 20);
+        assert_eq!(options.first_seed, 7);
+        assert_eq!(options.skill.reaction_delay, 0.5);
+        assert_eq!(options.skill.miss_rate, 0.2);
+        assert!(options.format == Format::Json);
+        assert_eq!(options.mode, GameMode::Short);
+        assert_eq!(args("").unwrap().runs, Options::default().runs);
+    }
+
+    #[test]
+    fn bad_flags_are_reported() {
+        for (line, error) in [
+            ("--runs", "missing value for --runs"),
+            ("--runs lots", "bad value for --runs: lots"),
+            ("--format xml", "unknown format xml"),
+            ("--mode endless", "unknown mode endless"),
+            ("--turbo 1", "unknown flag --turbo"),
+        ] {
+            assert_eq!(args(line).err().as_deref(), Some(error), "{line}");
+        }
+    }
+
+    #[test]
+    fn summary_splits_microgames_by_tier() {
+        let reports = [
+            report(
+                true,
+                vec![
+                    result("pipes", 5.0, Some(1.0)),
+                    result("pipes", 4.0, None),
+                    result("swap", 4.0, Some(2.0)),
+                ],
+            ),
+            report(
+                false,
+                vec![result("pipes", 5.0, Some(3.0)), result("swap", 5.0, None)],
+            ),
+            report(false, vec![result("pipes", 5.0, None)]),
+        ];
+        let summary = summarize(&reports, 3);
+        assert_eq!((summary.runs, summary.runs_won), (3, 1));
+
+        let five = &summary.tiers[&("pipes", 5000)];
+        assert_eq!((five.plays, five.wins), (3, 2));
+        assert_eq!(five.avg_win_time(), Some(2.0));
+        let four = &summary.tiers[&("pipes", 4000)];
+        assert_eq!(four.win_rate(), 0.0);
+        assert_eq!(four.avg_win_time(), None);
+        assert_eq!(summary.tiers.len(), 4);
+
+        // Runs drop out once they've played as many microgames as they got through,
+        // the won run counts as alive right to the end
+        let thirds = [1.0, 2.0 / 3.0, 1.0 / 3.0, 1.0 / 3.0];
+        assert_eq!(summary.survival, thirds);
+    }
+}
@@ -0,0 +1,40 @@
+//! Relay for online versus, pairs a host with whoever types in their room code
+//!
+//! cargo run --bin relay -- --bind 0.0.0.0:7777
+
+use std::{env, net::TcpListener, process};
+
+use microship::game::net::{run_relay, DEFAULT_RELAY};
+
+const USAGE: &str = "usage: relay [--bind ADDRESS:PORT]";
+
+fn parse_args() -> Result<String, String> {
+    let mut bind = DEFAULT_RELAY.to_string();
+    let mut args = env::args().skip(1);
+    while let Some(flag) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("missing value for {flag}"))?;
+        match flag.as_str() {
+            "--bind" => bind = value,
+            _ => return Err(format!("unknown flag {flag}")),
+        }
+    }
+    Ok(bind)
+}
+
+fn main() {
+    let bind = parse_args().unwrap_or_else(|e| {
+        eprintln!("{e}\n{USAGE}");
+        process::exit(2);
+    });
+    let listener = TcpListener::bind(&bind).unwrap_or_else(|e| {
+        eprintln!("couldn't listen on {bind}: {e}");
+        process::exit(1);
+    });
+    eprintln!("relay listening on {bind}");
+    if let Err(e) = run_relay(listener) {
+        eprintln!("relay stopped: {e}");
+        process::exit(1);
+    }
+}
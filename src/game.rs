@@ -1,23 +1,122 @@
 #![allow(dead_code, unused)]
-use std::{collections::HashMap, usize};
+use std::collections::HashMap;
 
+use achievements::{Achievements, GalleryData};
+use camera::CameraFx;
+use daily::{DailyResult, DailySummary, Date};
+use duel::DuelData;
+use events::{Listener, Stats};
 use gameplay::InGameData;
+use ghost::Ghost;
+use history::{HistoryData, RunHistory, RunRecord};
+use i18n::Strings;
 use macroquad::{
     audio::{load_sound_from_bytes, play_sound_once, stop_sound, Sound},
     prelude::*,
 };
 use main_menu::MainMenuData;
-
+use online::{LobbyData, OnlineMatch};
+use options_menu::OptionsData;
+use party::PlayStyle;
+use results::ResultsData;
+use rng::Rng;
+use run_config::GameMode;
+use score::ScoreSummary;
+use settings::{Accessibility, Settings};
+use summary::SummaryData;
+use tween::{Ease, Lerp, Tween};
+
+mod achievements;
+pub mod bot;
+mod camera;
+mod daily;
+#[cfg(feature = "dev")]
+mod dev;
+mod duel;
+mod events;
 mod gameplay;
+mod ghost;
+mod history;
+mod hud;
+mod i18n;
 mod main_menu;
 mod microgames;
+pub mod net;
+mod online;
+mod options_menu;
+mod particles;
+mod party;
+mod queue;
+mod results;
+mod rng;
+pub mod run_config;
+mod save;
+mod score;
+mod settings;
+pub mod sim;
+mod summary;
+mod tween;
+mod ui;
 
 #[derive(Debug, PartialEq)]
 pub enum GameEvents {
     StartGameplay,
     GameWon,
     GameLost,
+    /// A party or versus run is over, time for the results
+    PartyOver,
     MainMenuReturn,
+    OpenOptions,
+    OpenLobby,
+    OpenGallery,
+    /// Also where a replay goes back to once it's over
+    OpenHistory,
+    /// Play back the run at this index of the history
+    WatchRun(usize),
+    /// Both players are in, an online run of `mode` starts from `seed`
+    StartOnline {
+        mode: GameMode,
+        seed: u64,
+    },
+    /// Play the same run again, `seed` deals the exact same microgames
+    RetryRun {
+        mode: GameMode,
+        seed: u64,
+    },
+    /// Adds trauma to the camera, 1.0 is the strongest shake
+    ScreenShake(f32),
+    /// Freezes gameplay for the given number of seconds
+    HitStop(f32),
+    /// Briefly zooms the camera in by the given fraction
+    ZoomPunch(f32),
+    /// The asteroid lazer went off, `first_frame` if it had only just lined up
+    LaserFired {
+        hit: bool,
+        first_frame: bool,
+    },
+    /// Doors opened on a microgame and its timer started
+    MicrogameStarted {
+        name: &'static str,
+        boss: bool,
+        stage: u8,
+        time_limit: f32,
+    },
+    LifeLost {
+        lives_left: u8,
+    },
+    /// A step of a microgame done right, like a combo button in order
+    ButtonPressed,
+    /// A misstep that sets the player back, like a combo button out of order
+    WrongInput,
+    CanSwapped,
+    /// A microgame's timer ran out, `won_at` is when it was won if it was
+    MicrogameEnded {
+        name: &'static str,
+        boss: bool,
+        stage: u8,
+        time_limit: f32,
+        won_at: Option<f32>,
+    },
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -27,56 +126,24 @@ enum TransState {
     TransOut(f32),
 }
 
-struct WinLooseData {
-    trans_state: TransState,
-}
-
+#[allow(clippy::large_enum_variant)]
 enum ActiveState {
     MainMenu(MainMenuData),
     InGame(InGameData),
-    WinScreen(WinLooseData),
-    LoseScreen(WinLooseData),
+    Options(OptionsData),
+    Summary(SummaryData),
+    Results(ResultsData),
+    Duel(DuelData),
+    Lobby(LobbyData),
+    Gallery(GalleryData),
+    History(HistoryData),
 }
 
 impl TransState {
     fn draw_doors(&self, textures: &HashMap<&str, Texture2D>) {
-        let r_door = textures.get("right_door").unwrap();
-        let l_door = textures.get("left_door").unwrap();
-        fn lerp(f: f32, t: f32, d: f32) -> f32 {
-            t * d + f * (1.0 - d)
-        }
-
         match self {
-            TransState::TransIn(t) => {
-                let raw_progress = clamp((0.5 - t) * 2.0, 0.0, 1.0);
-                draw_texture(
-                    l_door,
-                    lerp(0.0, -500.0, raw_progress * raw_progress),
-                    0.0,
-                    WHITE,
-                );
-                draw_texture(
-                    r_door,
-                    lerp(462.0, 1000.0, raw_progress * raw_progress),
-                    0.0,
-                    WHITE,
-                );
-            }
-            TransState::TransOut(t) => {
-                let raw_progress = clamp((0.5 - t) * 2.0, 0.0, 1.0);
-                draw_texture(
-                    l_door,
-                    lerp(-500.0, 0.0, raw_progress * raw_progress),
-                    0.0,
-                    WHITE,
-                );
-                draw_texture(
-                    r_door,
-                    lerp(1000.0, 462.0, raw_progress * raw_progress),
-                    0.0,
-                    WHITE,
-                );
-            }
+            TransState::TransIn(t) => draw_doors(textures, doors_opening(0.5).sample(0.5 - t)),
+            TransState::TransOut(t) => draw_doors(textures, doors_closing(0.5).sample(0.5 - t)),
             _ => {}
         };
     }
@@ -113,10 +180,43 @@ impl TransState {
     }
 }
 
+/// Doors sliding apart, sampled as openness for `draw_doors`
+fn doors_opening(duration: f32) -> Tween<f32> {
+    Tween::new(0.0, 1.0, duration, Ease::InQuad)
+}
+
+/// Doors sliding shut, sampled as openness for `draw_doors`
+fn doors_closing(duration: f32) -> Tween<f32> {
+    Tween::new(1.0, 0.0, duration, Ease::InQuad)
+}
+
+/// Draws the transition doors, `openness` runs from 0.0 shut to 1.0 fully open
+fn draw_doors(textures: &HashMap<&str, Texture2D>, openness: f32) {
+    let r_door = textures.get("right_door").unwrap();
+    let l_door = textures.get("left_door").unwrap();
+    draw_texture(l_door, Lerp::lerp(0.0, -500.0, openness), 0.0, WHITE);
+    draw_texture(r_door, Lerp::lerp(462.0, 1000.0, openness), 0.0, WHITE);
+}
+
 pub struct GameState {
     active_state: ActiveState,
     textures: HashMap<&'static str, Texture2D>,
     song: Sound,
+    settings: Settings,
+    camera_fx: CameraFx,
+    strings: Strings,
+    /// Offscreen halves for split-screen duels
+    split_screen: [RenderTarget; 2],
+    /// Set while playing online, and kept through the results that follow
+    online: Option<OnlineMatch>,
+    /// Day a solo daily run was started on, its best is kept under that date
+    daily: Option<Date>,
+    /// Mode of the solo run being played, a retry can be in a different one than the menu's
+    run_mode: GameMode,
+    achievements: Achievements,
+    stats: Stats,
+    #[cfg(feature = "dev")]
+    dev: dev::DevTools,
 }
 
 #[derive(Debug, PartialEq)]
@@ -126,61 +226,56 @@ enum MousePressState {
     Pressed,
 }
 
+/// Second player's keyboard controls, WASD moves their cursor and E presses
+#[derive(Default)]
+struct PadInput {
+    /// Not normalized, diagonals come out longer
+    dir: Vec2,
+    held: bool,
+    pressed: bool,
+}
+
 pub struct FrameInput {
     mouse_position: Vec2,
     mouse_state: MousePressState,
+    /// Space or enter held down
+    key_held: bool,
+    /// Set by gameplay when drag assist is on and the player is holding a button,
+    /// drag based microgames progress on their own while this is true
+    assist: bool,
+    /// Only read by split-screen duels
+    p2: PadInput,
+    /// Characters typed this frame, backspace comes through as '\u{8}' and enter as '\n'
+    typed: String,
 }
 
-pub enum ThreadLoadResource {
-    Textures(HashMap<&'static str, Image>),
-    MainSong(Sound),
-}
-
-pub struct Particle {
-    pub position: Vec2,
-    pub velocity: Vec2,
-    pub rotation: f32,
-    pub ang_velocity: f32,
-    pub lifetime: f32,
-    pub color: Color,
-    pub color_delta: Color,
+impl FrameInput {
+    /// Marks the input as assisted when drag assist is on and a button is held
+    fn with_assist(self, access: &Accessibility) -> FrameInput {
+        FrameInput {
+            assist: access.drag_assist
+                && (self.key_held || self.mouse_state != MousePressState::NotPressed),
+            ..self
+        }
+    }
 }
 
-pub fn particles_update(particles: &mut Vec<Particle>, delta: &f32) {
-    let mut remove_queue: Vec<usize> = vec![];
-    let d = *delta;
-    for (p, i) in particles.iter_mut().zip(0..) {
-        p.position += p.velocity * d;
-        p.rotation += p.ang_velocity * d;
-        p.color.r += p.color_delta.r * *delta;
-        p.color.g += p.color_delta.g * *delta;
-        p.color.b += p.color_delta.b * *delta;
-        p.color.a = p.color.a - (1.0 * d);
-        p.lifetime -= *delta;
-        if p.lifetime <= 0.0 {
-            remove_queue.push(i);
+impl Default for FrameInput {
+    fn default() -> Self {
+        FrameInput {
+            mouse_position: Vec2::ZERO,
+            mouse_state: MousePressState::NotPressed,
+            key_held: false,
+            assist: false,
+            p2: PadInput::default(),
+            typed: String::new(),
         }
     }
-    for i in remove_queue.into_iter().rev() {
-        particles.swap_remove(i);
-    }
 }
 
-pub fn particles_draw(particles: &Vec<Particle>, tex: &Texture2D) {
-    let w = tex.width() / 2.0;
-    let h = tex.height() / 2.0;
-    for p in particles.iter() {
-        draw_texture_ex(
-            tex,
-            p.position.x - w,
-            p.position.y - h,
-            p.color,
-            DrawTextureParams {
-                rotation: p.rotation,
-                ..Default::default()
-            },
-        );
-    }
+pub enum ThreadLoadResource {
+    Textures(HashMap<&'static str, Image>),
+    MainSong(Sound),
 }
 
 pub fn vec2_in_range(v: &Vec2, r: &(Vec2, Vec2)) -> bool {
@@ -202,6 +297,31 @@ pub fn gather_frame_input() -> FrameInput {
                 MousePressState::NotPressed
             }
         },
+        key_held: is_key_down(KeyCode::Space) || is_key_down(KeyCode::Enter),
+        assist: false,
+        p2: {
+            let axis = |neg, pos| (is_key_down(pos) as i32 - is_key_down(neg) as i32) as f32;
+            PadInput {
+                dir: Vec2::new(axis(KeyCode::A, KeyCode::D), axis(KeyCode::W, KeyCode::S)),
+                held: is_key_down(KeyCode::E),
+                pressed: is_key_pressed(KeyCode::E),
+            }
+        },
+        typed: {
+            let mut typed = String::new();
+            while let Some(c) = get_char_pressed() {
+                if !c.is_control() {
+                    typed.push(c);
+                }
+            }
+            if is_key_pressed(KeyCode::Backspace) {
+                typed.push('\u{8}');
+            }
+            if is_key_pressed(KeyCode::Enter) {
+                typed.push('\n');
+            }
+            typed
+        },
     }
 }
 
@@ -325,7 +445,7 @@ pub fn get_texture_images() -> HashMap<&'static str, Image> {
     include_texture!(textures, "swap_plate", "../assets/sort_plate.png");
     include_texture!(textures, "crank_plate", "../assets/crank_plate.png");
 
-    return textures;
+    textures
 }
 
 pub async fn load_song() -> Sound {
@@ -343,75 +463,266 @@ pub fn init_game_state(images: HashMap<&'static str, Image>, song: Sound) -> Gam
 
     //build_textures_atlas();
 
+    let settings = Settings::load();
+    let mut camera_fx = CameraFx::default();
+    camera_fx.enabled = settings.screen_effects;
+
     GameState {
         song,
         textures,
         active_state: ActiveState::MainMenu(MainMenuData::default()),
+        strings: Strings::new(settings.language),
+        settings,
+        camera_fx,
+        split_screen: [(); 2].map(|_| {
+            let target = render_target(960, 600);
+            target.texture.set_filter(FilterMode::Linear);
+            target
+        }),
+        online: None,
+        daily: None,
+        run_mode: GameMode::Standard,
+        achievements: Achievements::load(),
+        stats: Stats::load(),
+        #[cfg(feature = "dev")]
+        dev: dev::DevTools::default(),
     }
 }
 
+// Err just means the game should close, there's nothing more to report
+#[allow(clippy::result_unit_err)]
 pub fn update_game_state(
     state: &mut GameState,
     input: FrameInput,
     deltatime: f32,
 ) -> Result<(), ()> {
+    #[cfg(feature = "dev")]
+    let Some((input, deltatime)) = dev::update(state, input, deltatime) else {
+        return Ok(());
+    };
+
+    // A hit-stop only holds time still, clicks made during it still count
+    state.camera_fx.update(deltatime);
+    let deltatime = state.camera_fx.sim_delta(deltatime);
+
     let mut events: Vec<GameEvents> = vec![];
     let out = match &state.active_state {
         ActiveState::MainMenu(_) => main_menu::update(state, input, deltatime, &mut events),
         ActiveState::InGame(_) => gameplay::update(state, input, deltatime, &mut events),
-        ActiveState::WinScreen(_) | ActiveState::LoseScreen(_) => {
-            if let ActiveState::WinScreen(t) = &mut state.active_state {
-                t.trans_state = t.trans_state.update(deltatime, &mut events, &input);
-            }
-            if let ActiveState::LoseScreen(t) = &mut state.active_state {
-                t.trans_state = t.trans_state.update(deltatime, &mut events, &input);
-            }
+        ActiveState::Options(_) => options_menu::update(state, input, deltatime, &mut events),
+        ActiveState::Results(_) => results::update(state, input, deltatime, &mut events),
+        ActiveState::Duel(_) => duel::update(state, input, deltatime, &mut events),
+        ActiveState::Lobby(_) => online::update(state, input, deltatime, &mut events),
+        ActiveState::Gallery(_) => achievements::update(state, input, deltatime, &mut events),
+        ActiveState::Summary(_) => summary::update(state, input, deltatime, &mut events),
+        ActiveState::History(_) => history::update(state, input, deltatime, &mut events),
+    };
 
-            Ok(())
+    // A replay still shakes the screen, but it was already counted when it was played
+    let watching = matches!(&state.active_state, ActiveState::InGame(data) if data.is_replay());
+    if watching {
+        events::dispatch(&events, &mut [&mut state.camera_fx]);
+    } else {
+        events::dispatch(
+            &events,
+            &mut [
+                &mut state.camera_fx,
+                &mut state.achievements,
+                &mut state.stats,
+            ],
+        );
+    }
+    state.achievements.update(deltatime);
+
+    if let Some(online) = &mut state.online {
+        if let ActiveState::InGame(data) = &state.active_state {
+            online.send_events(&events, data.lives());
         }
-        _ => todo!(),
-    };
+        online.receive();
+        if let ActiveState::Results(results) = &mut state.active_state {
+            results.refresh(online.standings(), online.waiting());
+        }
+    }
 
     if events.contains(&GameEvents::StartGameplay) {
-        let mut new_game_state = InGameData::default();
-        state.active_state = ActiveState::InGame(new_game_state);
+        let mode = state.settings.mode;
+        let config = mode.config();
+        let date = Date::today();
+        let rng = match mode {
+            GameMode::Daily => Rng::new(date.seed()),
+            _ => Rng::from_global(),
+        };
+        let style = state.settings.style;
+        state.daily = (mode == GameMode::Daily && style == PlayStyle::Solo).then_some(date);
+        state.run_mode = mode;
+        if style == PlayStyle::Solo {
+            state.achievements.track_run(mode);
+        }
+        state.active_state = match style {
+            PlayStyle::Duel => ActiveState::Duel(DuelData::new(config, rng)),
+            style => ActiveState::InGame(InGameData::with_style(config, style, rng)),
+        };
         play_sound_once(&state.song);
     };
 
-    if events.contains(&GameEvents::GameWon) {
-        state.active_state = ActiveState::WinScreen(WinLooseData {
-            trans_state: TransState::TransIn(0.5),
-        });
+    let retry = events.iter().find_map(|e| match e {
+        GameEvents::RetryRun { mode, seed } => Some((*mode, *seed)),
+        _ => None,
+    });
+    if let Some((mode, seed)) = retry {
+        // Only today's daily counts towards the day's best
+        let today = Date::today();
+        state.daily = (mode == GameMode::Daily && seed == today.seed()).then_some(today);
+        state.run_mode = mode;
+        state.achievements.track_run(mode);
+        let ghost = RunHistory::load()
+            .best_replay(mode, seed)
+            .map(|replay| Ghost::new(mode.config(), seed, replay.clone()));
+        state.active_state =
+            ActiveState::InGame(InGameData::new(mode.config(), Rng::new(seed)).with_ghost(ghost));
+        play_sound_once(&state.song);
+    }
+
+    let watch = events.iter().find_map(|e| match (e, &state.active_state) {
+        (GameEvents::WatchRun(index), ActiveState::History(data)) => data.run(*index).cloned(),
+        _ => None,
+    });
+    if let Some(RunRecord {
+        mode,
+        seed,
+        replay: Some(replay),
+        ..
+    }) = watch
+    {
+        state.daily = None;
+        state.run_mode = mode;
+        state.active_state =
+            ActiveState::InGame(InGameData::replaying(mode.config(), Rng::new(seed), replay));
+        play_sound_once(&state.song);
     }
 
-    if events.contains(&GameEvents::GameLost) {
-        state.active_state = ActiveState::LoseScreen(WinLooseData {
-            trans_state: TransState::TransIn(0.5),
+    let online_start = events.iter().find_map(|e| match e {
+        GameEvents::StartOnline { mode, seed } => Some((*mode, *seed)),
+        _ => None,
+    });
+    if let Some((mode, seed)) = online_start {
+        let client = match &mut state.active_state {
+            ActiveState::Lobby(data) => data.take_client(),
+            _ => None,
+        };
+        if let Some(client) = client {
+            let config = mode.config();
+            state.online = Some(OnlineMatch::new(client, config.lives, config.length));
+            state.active_state = ActiveState::InGame(InGameData::new(config, Rng::new(seed)));
+            play_sound_once(&state.song);
+        }
+    }
+
+    // Online runs end on the shared results instead, where the opponent can still catch up
+    let run_over = events.contains(&GameEvents::GameWon) || events.contains(&GameEvents::GameLost);
+    if let (Some(online), true) = (&state.online, run_over) {
+        state.active_state =
+            ActiveState::Results(ResultsData::online(online.standings(), online.waiting()));
+    } else if let (true, ActiveState::InGame(data)) = (run_over, &state.active_state) {
+        let won = events.contains(&GameEvents::GameWon);
+        let mode = state.run_mode;
+        if !data.is_replay() {
+            let mut history = RunHistory::load();
+            history.add(RunRecord::new(
+                mode,
+                data.seed(),
+                won,
+                data.score(),
+                data.history().to_vec(),
+                data.recording()
+                    .filter(|_| state.settings.save_replays)
+                    .cloned(),
+            ));
+            history.save();
+        }
+        let daily = state.daily.take().map(|date| {
+            DailySummary::record(
+                date,
+                DailyResult::from_history(won, data.history()),
+                data.config().length,
+            )
         });
+        state.active_state = if data.is_replay() {
+            ActiveState::History(HistoryData::load())
+        } else {
+            ActiveState::Summary(SummaryData::new(
+                won,
+                mode,
+                data.seed(),
+                data.history().to_vec(),
+                ScoreSummary::record(mode, data.score(), won),
+                daily,
+            ))
+        };
+    }
+
+    if events.contains(&GameEvents::PartyOver) {
+        let results = match &state.active_state {
+            ActiveState::InGame(data) => Some(ResultsData::new(data.style(), data.standings())),
+            ActiveState::Duel(data) => Some(ResultsData::new(PlayStyle::Duel, data.standings())),
+            _ => None,
+        };
+        if let Some(results) = results {
+            state.active_state = ActiveState::Results(results);
+        }
+    }
+
+    if events.contains(&GameEvents::OpenOptions) {
+        state.active_state = ActiveState::Options(OptionsData::default());
+    }
+
+    if events.contains(&GameEvents::OpenLobby) {
+        state.active_state = ActiveState::Lobby(LobbyData::default());
+    }
+
+    if events.contains(&GameEvents::OpenGallery) {
+        state.active_state = ActiveState::Gallery(GalleryData::default());
+    }
+
+    if events.contains(&GameEvents::OpenHistory) {
+        state.active_state = ActiveState::History(HistoryData::load());
+        stop_sound(&state.song);
     }
 
     if events.contains(&GameEvents::MainMenuReturn) {
         state.active_state = ActiveState::MainMenu(MainMenuData::default());
+        state.online = None;
+        state.daily = None;
         stop_sound(&state.song);
     }
 
-    return out;
+    out
 }
 
+#[allow(clippy::result_unit_err)]
 pub fn draw_game_state(state: &GameState) -> Result<(), ()> {
-    match &state.active_state {
+    set_camera(&state.camera_fx.camera());
+    let out = match &state.active_state {
         ActiveState::MainMenu(_) => main_menu::draw(state),
-        ActiveState::InGame(_) => gameplay::draw(state),
-        ActiveState::LoseScreen(t_state) => {
-            draw_texture(state.textures.get("lose_screen").unwrap(), 0.0, 0.0, WHITE);
-            t_state.trans_state.draw_doors(&state.textures);
-            Ok(())
-        }
-        ActiveState::WinScreen(t_state) => {
-            draw_texture(state.textures.get("win_screen").unwrap(), 0.0, 0.0, WHITE);
-            t_state.trans_state.draw_doors(&state.textures);
-            Ok(())
-        }
-        _ => todo!(),
-    }
+        ActiveState::InGame(_) => gameplay::draw(state).map(|_| {
+            if let Some(online) = &state.online {
+                online.draw_opponent(state);
+            }
+        }),
+        ActiveState::Options(_) => options_menu::draw(state),
+        ActiveState::Results(_) => results::draw(state),
+        ActiveState::Duel(_) => duel::draw(state),
+        ActiveState::Lobby(_) => online::draw(state),
+        ActiveState::Gallery(_) => achievements::draw(state),
+        ActiveState::Summary(_) => summary::draw(state),
+        ActiveState::History(_) => history::draw(state),
+    };
+    #[cfg(feature = "dev")]
+    dev::draw_world(state);
+    set_default_camera();
+    state.achievements.draw_toast(&state.strings);
+    #[cfg(feature = "dev")]
+    dev::draw_overlay(state);
+
+    out
 }
@@ -0,0 +1,419 @@
+use std::collections::VecDeque;
+
+use macroquad::prelude::*;
+
+use super::{
+    events::Listener,
+    i18n::Strings,
+    run_config::GameMode,
+    save,
+    tween::{Ease, Tween},
+    ui::{draw_text_centered, Button},
+    ActiveState, FrameInput, GameEvents, GameState,
+};
+
+const ACHIEVEMENTS_FILE: &str = "achievements.txt";
+const TOAST_TIME: f32 = 3.0;
+const BACK_BUTTON: Button = Button::new(380.0, 540.0, 200.0, 48.0);
+
+/// What has to happen for an achievement to unlock
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Goal {
+    WinRun,
+    /// Win a run without losing a single life
+    FlawlessRun,
+    /// Win the named microgame less than `secs` in
+    QuickWin {
+        name: &'static str,
+        secs: f32,
+    },
+    /// Hit the asteroid on the very first frame the lazer lines up with it
+    FirstFrameShot,
+    BeatBoss,
+    /// Win this many microgames in one solo run of `mode`, lives lost along the way or not
+    RunWins {
+        mode: GameMode,
+        wins: u32,
+    },
+}
+
+pub struct Achievement {
+    pub id: &'static str,
+    pub goal: Goal,
+}
+
+impl Achievement {
+    pub fn name_key(&self) -> String {
+        format!("achievement.{}", self.id)
+    }
+
+    pub fn desc_key(&self) -> String {
+        format!("achievement.{}_desc", self.id)
+    }
+}
+
+pub static ACHIEVEMENTS: [Achievement; 6] = [
+    Achievement {
+        id: "first_shift",
+        goal: Goal::WinRun,
+    },
+    Achievement {
+        id: "spotless",
+        goal: Goal::FlawlessRun,
+    },
+    Achievement {
+        id: "neat_freak",
+        goal: Goal::QuickWin {
+            name: "swap",
+            secs: 1.0,
+        },
+    },
+    Achievement {
+        id: "dead_eye",
+        goal: Goal::FirstFrameShot,
+    },
+    Achievement {
+        id: "boss_sweeper",
+        goal: Goal::BeatBoss,
+    },
+    Achievement {
+        id: "veteran",
+        goal: Goal::RunWins {
+            mode: GameMode::Marathon,
+            wins: 30,
+        },
+    },
+];
+
+/// Unlocks, progress through the current run and the toasts still on screen
+#[derive(Default)]
+pub struct Achievements {
+    unlocked: Vec<&'static str>,
+    /// Mode of the current run, None unless it's a solo one
+    run_mode: Option<GameMode>,
+    /// Lives lost since the current run started
+    run_losses: u32,
+    /// Microgames won since the current run started
+    run_wins: u32,
+    /// Something worth saving changed since the last save
+    dirty: bool,
+    /// Newest last, with how long each has been showing
+    toasts: VecDeque<(&'static Achievement, f32)>,
+}
+
+impl Achievements {
+    pub fn load() -> Self {
+        let kv = save::read_kv(ACHIEVEMENTS_FILE);
+        let saved: Vec<&str> = kv
+            .get("unlocked")
+            .map(|u| u.split(',').map(str::trim).collect())
+            .unwrap_or_default();
+        Achievements {
+            // Unknown ids from other versions are dropped
+            unlocked: ACHIEVEMENTS
+                .iter()
+                .map(|a| a.id)
+                .filter(|id| saved.contains(id))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn save(&self) {
+        let _ = save::write_kv(ACHIEVEMENTS_FILE, &[("unlocked", self.unlocked.join(","))]);
+    }
+
+    pub fn is_unlocked(&self, id: &str) -> bool {
+        self.unlocked.contains(&id)
+    }
+
+    /// Lets the run that just started count towards goals for `mode`, party, duel and
+    /// online runs never call this
+    pub fn track_run(&mut self, mode: GameMode) {
+        self.run_mode = Some(mode);
+    }
+
+    fn met(&self, goal: Goal, event: &GameEvents) -> bool {
+        match (goal, event) {
+            (Goal::WinRun, GameEvents::GameWon) => true,
+            (Goal::FlawlessRun, GameEvents::GameWon) => self.run_losses == 0,
+            (
+                Goal::QuickWin { name, secs },
+                GameEvents::MicrogameEnded {
+                    name: played,
+                    won_at: Some(t),
+                    ..
+                },
+            ) => *played == name && *t < secs,
+            (Goal::FirstFrameShot, GameEvents::LaserFired { hit, first_frame }) => {
+                *hit && *first_frame
+            }
+            (
+                Goal::BeatBoss,
+                GameEvents::MicrogameEnded {
+                    boss: true,
+                    won_at: Some(_),
+                    ..
+                },
+            ) => true,
+            (Goal::RunWins { mode, wins }, GameEvents::MicrogameEnded { .. }) => {
+                self.run_mode == Some(mode) && self.run_wins >= wins
+            }
+            _ => false,
+        }
+    }
+
+    /// Tracks this frame's events, giving back anything they unlocked
+    pub fn handle(&mut self, events: &[GameEvents]) -> Vec<&'static Achievement> {
+        let mut unlocked = vec![];
+        for event in events {
+            match event {
+                GameEvents::StartGameplay
+                | GameEvents::StartOnline { .. }
+                | GameEvents::RetryRun { .. } => {
+                    self.run_mode = None;
+                    self.run_losses = 0;
+                    self.run_wins = 0;
+                }
+                GameEvents::LifeLost { .. } => self.run_losses += 1,
+                GameEvents::MicrogameEnded { won_at, .. } => {
+                    self.run_wins += won_at.is_some() as u32
+                }
+                _ => {}
+            }
+            for achievement in ACHIEVEMENTS.iter() {
+                if !self.is_unlocked(achievement.id) && self.met(achievement.goal, event) {
+                    self.unlocked.push(achievement.id);
+                    self.toasts.push_back((achievement, 0.0));
+                    self.dirty = true;
+                    unlocked.push(achievement);
+                }
+            }
+        }
+        unlocked
+    }
+
+    /// Saves anything new and moves the toasts along
+    pub fn update(&mut self, delta: f32) {
+        if self.dirty {
+            self.save();
+            self.dirty = false;
+        }
+
+        // One toast at a time, the rest wait their turn
+        if let Some((_, t)) = self.toasts.front_mut() {
+            *t += delta;
+            if *t > TOAST_TIME {
+                self.toasts.pop_front();
+            }
+        }
+    }
+
+    /// Slides the current toast down from the top of the screen
+    pub fn draw_toast(&self, strings: &Strings) {
+        let Some((achievement, t)) = self.toasts.front() else {
+            return;
+        };
+        let drop = Tween::new(-80.0, 12.0, 0.35, Ease::OutBack).sample(*t)
+            - Tween::new(0.0, 92.0, 0.3, Ease::InQuad)
+                .with_delay(TOAST_TIME - 0.3)
+                .sample(*t);
+        draw_rectangle(240.0, drop, 480.0, 64.0, Color::new(0.05, 0.05, 0.08, 0.9));
+        draw_rectangle_lines(240.0, drop, 480.0, 64.0, 3.0, GOLD);
+        draw_text_centered(
+            strings.get("achievement.unlocked"),
+            Vec2::new(480.0, drop + 18.0),
+            20.0,
+            GOLD,
+        );
+        draw_text_centered(
+            strings.get(&achievement.name_key()),
+            Vec2::new(480.0, drop + 44.0),
+            32.0,
+            WHITE,
+        );
+    }
+}
+
+impl Listener for Achievements {
+    fn handle_event(&mut self, event: &GameEvents) {
+        self.handle(std::slice::from_ref(event));
+    }
+}
+
+/// Every achievement with how close the player is, opened from the main menu
+pub struct GalleryData {
+    mouse_pos: Vec2,
+}
+
+impl Default for GalleryData {
+    fn default() -> Self {
+        GalleryData {
+            mouse_pos: Vec2::ZERO,
+        }
+    }
+}
+
+pub fn update(
+    game_data: &mut GameState,
+    input: FrameInput,
+    delta: f32,
+    events: &mut Vec<GameEvents>,
+) -> Result<(), ()> {
+    if let ActiveState::Gallery(g_data) = &mut game_data.active_state {
+        g_data.mouse_pos = input.mouse_position;
+        if BACK_BUTTON.clicked(&input) {
+            events.push(GameEvents::MainMenuReturn);
+        }
+        Ok(())
+    } else {
+        Err(())
+    }
+}
+
+pub fn draw(game_data: &GameState) -> Result<(), ()> {
+    if let ActiveState::Gallery(g_data) = &game_data.active_state {
+        let strings = &game_data.strings;
+        let progress = &game_data.achievements;
+        clear_background(Color::new(0.08, 0.08, 0.12, 1.0));
+        draw_text_centered(
+            strings.get("achievement.title"),
+            Vec2::new(480.0, 60.0),
+            64.0,
+            WHITE,
+        );
+
+        for (i, achievement) in ACHIEVEMENTS.iter().enumerate() {
+            let y = 110.0 + 70.0 * i as f32;
+            let unlocked = progress.is_unlocked(achievement.id);
+            let (fill, name_color) = if unlocked {
+                (Color::new(0.2, 0.17, 0.05, 0.9), GOLD)
+            } else {
+                (Color::new(0.0, 0.0, 0.0, 0.5), GRAY)
+            };
+            draw_rectangle(120.0, y, 720.0, 60.0, fill);
+            draw_text(
+                strings.get(&achievement.name_key()),
+                140.0,
+                y + 26.0,
+                30.0,
+                name_color,
+            );
+            draw_text(
+                strings.get(&achievement.desc_key()),
+                140.0,
+                y + 50.0,
+                22.0,
+                LIGHTGRAY,
+            );
+        }
+
+        BACK_BUTTON.draw(strings.get("menu.back"), &g_data.mouse_pos);
+        Ok(())
+    } else {
+        Err(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::i18n::Language;
+
+    fn ended(name: &'static str, boss: bool, won_at: Option<f32>) -> GameEvents {
+        GameEvents::MicrogameEnded {
+            name,
+            boss,
+            stage: 0,
+            time_limit: 5.0,
+            won_at,
+        }
+    }
+
+    fn ids(unlocked: Vec<&'static Achievement>) -> Vec<&'static str> {
+        unlocked.into_iter().map(|a| a.id).collect()
+    }
+
+    #[test]
+    fn flawless_needs_a_whole_run_without_losses() {
+        let mut progress = Achievements::default();
+        progress.handle(&[
+            GameEvents::StartGameplay,
+            GameEvents::LifeLost { lives_left: 2 },
+            ended("pipes", false, None),
+        ]);
+        assert_eq!(
+            ids(progress.handle(&[GameEvents::GameWon])),
+            ["first_shift"]
+        );
+
+        progress.handle(&[GameEvents::StartGameplay, ended("pipes", false, Some(2.0))]);
+        assert_eq!(ids(progress.handle(&[GameEvents::GameWon])), ["spotless"]);
+    }
+
+    #[test]
+    fn microgame_goals_check_the_details() {
+        let mut progress = Achievements::default();
+        assert!(progress
+            .handle(&[ended("swap", false, Some(1.2))])
+            .is_empty());
+        assert!(progress
+            .handle(&[ended("combo", false, Some(0.5))])
+            .is_empty());
+        assert_eq!(
+            ids(progress.handle(&[ended("swap", false, Some(0.8))])),
+            ["neat_freak"]
+        );
+        assert_eq!(
+            ids(progress.handle(&[ended("boss_pipes", true, Some(9.0))])),
+            ["boss_sweeper"]
+        );
+
+        let shot = |first_frame| GameEvents::LaserFired {
+            hit: true,
+            first_frame,
+        };
+        assert!(progress.handle(&[shot(false)]).is_empty());
+        assert_eq!(ids(progress.handle(&[shot(true)])), ["dead_eye"]);
+        // Only ever unlocks once
+        assert!(progress.handle(&[shot(true)]).is_empty());
+    }
+
+    #[test]
+    fn veteran_needs_the_wins_in_one_marathon_run() {
+        let mut progress = Achievements::default();
+        let wins =
+            |n| -> Vec<GameEvents> { (0..n).map(|_| ended("pipes", false, Some(2.0))).collect() };
+
+        // A standard run, then a marathon that falls one short
+        progress.handle(&[GameEvents::StartGameplay]);
+        progress.track_run(GameMode::Standard);
+        assert!(progress.handle(&wins(30)).is_empty());
+        progress.handle(&[GameEvents::StartGameplay]);
+        progress.track_run(GameMode::Marathon);
+        assert!(progress.handle(&wins(29)).is_empty());
+
+        // Party and online runs aren't tracked, whatever their mode
+        progress.handle(&[GameEvents::StartGameplay]);
+        assert!(progress.handle(&wins(30)).is_empty());
+
+        progress.handle(&[GameEvents::RetryRun {
+            mode: GameMode::Marathon,
+            seed: 0,
+        }]);
+        progress.track_run(GameMode::Marathon);
+        progress.handle(&wins(29));
+        assert!(progress.handle(&[ended("pipes", false, None)]).is_empty());
+        assert_eq!(ids(progress.handle(&wins(1))), ["veteran"]);
+    }
+
+    #[test]
+    fn every_achievement_has_english_text() {
+        let strings = Strings::new(Language::English);
+        for achievement in ACHIEVEMENTS.iter() {
+            for key in [achievement.name_key(), achievement.desc_key()] {
+                assert_ne!(strings.get(&key), key, "missing {key}");
+            }
+        }
+    }
+}
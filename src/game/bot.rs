@@ -0,0 +1,236 @@
+use macroquad::prelude::*;
+
+use super::{
+    microgames::{MicrogameStatus, Microgames},
+    rng::Rng,
+    FrameInput, GameEvents, MousePressState,
+};
+
+/// What a scripted player wants to do with the mouse this frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BotAction {
+    Idle,
+    /// Move to the point and click it
+    Click(Vec2),
+    /// Move to the point with the button held, pressing first if needed
+    Hold(Vec2),
+}
+
+impl BotAction {
+    fn target(&self) -> Option<Vec2> {
+        match self {
+            BotAction::Idle => None,
+            BotAction::Click(p) | BotAction::Hold(p) => Some(*p),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BotSkill {
+    /// Cursor travel in pixels per second
+    pub cursor_speed: f32,
+    /// Seconds between a new target showing up and the bot reacting to it
+    pub reaction_delay: f32,
+    /// Chance for any click to land somewhere random near the target
+    pub miss_rate: f32,
+}
+
+impl BotSkill {
+    pub const PERFECT: BotSkill = BotSkill {
+        cursor_speed: f32::INFINITY,
+        reaction_delay: 0.0,
+        miss_rate: 0.0,
+    };
+}
+
+/// Distance a target has to jump before the bot treats it as new and has to react again
+const RETARGET_DISTANCE: f32 = 40.0;
+const MISS_OFFSET: (f32, f32) = (60.0, 160.0);
+
+/// Turns `BotAction`s into `FrameInput`s the way a player with the given skill would
+pub struct Bot {
+    pub skill: BotSkill,
+    pub cursor: Vec2,
+    pressed: bool,
+    last_target: Option<Vec2>,
+    reaction: f32,
+    rng: Rng,
+}
+
+impl Bot {
+    pub fn new(skill: BotSkill, seed: u64) -> Self {
+        Bot {
+            skill,
+            cursor: Vec2::new(480.0, 300.0),
+            pressed: false,
+            last_target: None,
+            reaction: 0.0,
+            rng: Rng::new(seed),
+        }
+    }
+
+    pub fn input(&mut self, action: BotAction, delta: f32) -> FrameInput {
+        let target = action.target();
+        let retargeted = match (self.last_target, target) {
+            (None, Some(_)) => true,
+            (Some(last), Some(new)) => last.distance(new) > RETARGET_DISTANCE,
+            _ => false,
+        };
+        // Someone mid-drag keeps holding while the target moves along with them
+        if retargeted && !self.pressed {
+            self.reaction = self.skill.reaction_delay;
+        }
+        self.last_target = target;
+        self.reaction -= delta;
+
+        let (Some(target), true) = (target, self.reaction <= 0.0) else {
+            self.pressed = false;
+            return self.frame(MousePressState::NotPressed);
+        };
+
+        let to_target = target - self.cursor;
+        let step = self.skill.cursor_speed * delta;
+        let arrived = to_target.length() <= step;
+        self.cursor = if arrived {
+            target
+        } else {
+            self.cursor + to_target.normalize() * step
+        };
+
+        let state = match action {
+            BotAction::Hold(_) if self.pressed => MousePressState::Pressed,
+            BotAction::Click(_) | BotAction::Hold(_) if arrived && !self.pressed => {
+                if self.rng.gen_range(0.0, 1.0) < self.skill.miss_rate {
+                    self.cursor += Vec2::from_angle(self.rng.gen_range(0.0, std::f32::consts::TAU))
+                        * self.rng.gen_range(MISS_OFFSET.0, MISS_OFFSET.1);
+                }
+                MousePressState::JustPressed
+            }
+            _ => MousePressState::NotPressed,
+        };
+        self.pressed = state != MousePressState::NotPressed;
+        self.frame(state)
+    }
+
+    fn frame(&self, mouse_state: MousePressState) -> FrameInput {
+        FrameInput {
+            mouse_position: self.cursor,
+            mouse_state,
+            ..Default::default()
+        }
+    }
+}
+
+/// Plays a microgame with the bot at a fixed frame rate, giving back how long it
+/// took to win, or None if it wasn't won when the time ran out
+pub fn simulate_microgame(
+    game: &mut Microgames,
+    bot: &mut Bot,
+    time_limit: f32,
+    delta: f32,
+) -> Option<f32> {
+    let mut events: Vec<GameEvents> = vec![];
+    let mut time = 0.0;
+    let mut won_at = None;
+    let mut status = MicrogameStatus::Running;
+    while time < time_limit {
+        let input = bot.input(game.bot_action(), delta);
+        status = game.update(input, delta, &mut events);
+        events.clear();
+        time += delta;
+        if status.is_won() && won_at.is_none() {
+            won_at = Some(time);
+        }
+        if status.is_decided() {
+            break;
+        }
+    }
+    // Gameplay only looks at the state when the timer runs out
+    if status.is_won() {
+        won_at
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::microgames::gen_microgame;
+
+    const SHORTEST_TIER: f32 = 3.0;
+    const FRAME: f32 = 1.0 / 60.0;
+
+    #[test]
+    fn every_microgame_is_winnable_in_the_shortest_tier() {
+        for id in 0..8 {
+            for seed in 0..250 {
+                let mut game = gen_microgame(id, &Rng::new(seed));
+                let mut bot = Bot::new(BotSkill::PERFECT, seed);
+                let result = simulate_microgame(&mut game, &mut bot, SHORTEST_TIER, FRAME);
+                assert!(result.is_some(), "microgame {id} lost with seed {seed}");
+            }
+        }
+    }
+
+    #[test]
+    fn bosses_are_winnable_in_their_own_time() {
+        for id in [8, 9] {
+            for seed in 0..100 {
+                let mut game = gen_microgame(id, &Rng::new(seed));
+                let time = game.fixed_time().unwrap();
+                let mut bot = Bot::new(BotSkill::PERFECT, seed);
+                let result = simulate_microgame(&mut game, &mut bot, time, FRAME);
+                assert!(result.is_some(), "boss {id} lost with seed {seed}");
+            }
+        }
+    }
+
+    #[test]
+    fn clicks_release_between_presses() {
+        let mut bot = Bot::new(BotSkill::PERFECT, 0);
+        let a = bot.input(BotAction::Click(Vec2::new(10.0, 10.0)), FRAME);
+        let b = bot.input(BotAction::Click(Vec2::new(500.0, 10.0)), FRAME);
+        let c = bot.input(BotAction::Click(Vec2::new(500.0, 10.0)), FRAME);
+        assert_eq!(a.mouse_state, MousePressState::JustPressed);
+        assert_eq!(b.mouse_state, MousePressState::NotPressed);
+        assert_eq!(c.mouse_state, MousePressState::JustPressed);
+    }
+
+    #[test]
+    fn slow_cursor_takes_time_to_arrive() {
+        let mut bot = Bot::new(
+            BotSkill {
+                cursor_speed: 600.0,
+                ..BotSkill::PERFECT
+            },
+            0,
+        );
+        bot.cursor = Vec2::ZERO;
+        let first = bot.input(BotAction::Click(Vec2::new(600.0, 0.0)), 0.5);
+        assert_eq!(first.mouse_state, MousePressState::NotPressed);
+        assert_eq!(first.mouse_position, Vec2::new(300.0, 0.0));
+        let second = bot.input(BotAction::Click(Vec2::new(600.0, 0.0)), 0.5);
+        assert_eq!(second.mouse_state, MousePressState::JustPressed);
+    }
+
+    #[test]
+    fn reaction_delay_holds_the_bot_back() {
+        let mut bot = Bot::new(
+            BotSkill {
+                reaction_delay: 0.2,
+                ..BotSkill::PERFECT
+            },
+            0,
+        );
+        let target = BotAction::Click(Vec2::new(100.0, 100.0));
+        assert_eq!(
+            bot.input(target, 0.1).mouse_state,
+            MousePressState::NotPressed
+        );
+        assert_eq!(
+            bot.input(target, 0.1).mouse_state,
+            MousePressState::JustPressed
+        );
+    }
+}
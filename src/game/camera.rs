@@ -0,0 +1,119 @@
+use macroquad::prelude::*;
+
+use super::{events::Listener, GameEvents};
+
+pub const SCREEN_SIZE: Vec2 = Vec2::new(960.0, 600.0);
+
+const MAX_SHAKE_OFFSET: f32 = 24.0;
+const MAX_SHAKE_ROTATION: f32 = 2.0;
+const TRAUMA_DECAY: f32 = 1.5;
+const ZOOM_DECAY: f32 = 6.0;
+
+/// Trauma based screen shake, hit-stop and zoom punches layered over
+/// the whole frame through a `Camera2D`
+pub struct CameraFx {
+    /// 0.0 to 1.0, shake strength is trauma squared
+    trauma: f32,
+    hit_stop: f32,
+    zoom_punch: f32,
+    time: f32,
+    pub enabled: bool,
+}
+
+impl Default for CameraFx {
+    fn default() -> Self {
+        CameraFx {
+            trauma: 0.0,
+            hit_stop: 0.0,
+            zoom_punch: 0.0,
+            time: 0.0,
+            enabled: true,
+        }
+    }
+}
+
+impl Listener for CameraFx {
+    fn handle_event(&mut self, event: &GameEvents) {
+        if !self.enabled {
+            return;
+        }
+        match event {
+            GameEvents::ScreenShake(amount) => {
+                self.trauma = clamp(self.trauma + amount, 0.0, 1.0);
+            }
+            GameEvents::HitStop(time) => {
+                self.hit_stop = self.hit_stop.max(*time);
+            }
+            GameEvents::ZoomPunch(amount) => {
+                self.zoom_punch = self.zoom_punch.max(*amount);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl CameraFx {
+    pub fn update(&mut self, delta: f32) {
+        self.time += delta;
+        self.hit_stop = (self.hit_stop - delta).max(0.0);
+        self.trauma = (self.trauma - TRAUMA_DECAY * delta).max(0.0);
+        self.zoom_punch *= (1.0 - ZOOM_DECAY * delta).max(0.0);
+        if !self.enabled {
+            self.clear();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.trauma = 0.0;
+        self.hit_stop = 0.0;
+        self.zoom_punch = 0.0;
+    }
+
+    /// Gameplay should hold still while this is true
+    pub fn is_frozen(&self) -> bool {
+        self.hit_stop > 0.0
+    }
+
+    /// Frame time the game should move on by, none at all during a hit-stop
+    pub fn sim_delta(&self, delta: f32) -> f32 {
+        if self.is_frozen() {
+            0.0
+        } else {
+            delta
+        }
+    }
+
+    pub fn camera(&self) -> Camera2D {
+        let mut camera =
+            Camera2D::from_display_rect(Rect::new(0.0, 0.0, SCREEN_SIZE.x, SCREEN_SIZE.y));
+
+        let shake = self.trauma * self.trauma;
+        // Layered sines instead of random numbers so the shake stays smooth
+        let noise = |seed: f32| {
+            (self.time * 37.0 + seed).sin() * 0.6 + (self.time * 71.0 + seed * 2.3).sin() * 0.4
+        };
+        camera.target += Vec2::new(noise(0.0), noise(10.0)) * MAX_SHAKE_OFFSET * shake;
+        camera.rotation = noise(20.0) * MAX_SHAKE_ROTATION * shake;
+        camera.zoom *= 1.0 + self.zoom_punch;
+        camera
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hit_stop_holds_the_frame_time() {
+        let mut camera = CameraFx::default();
+        camera.handle_event(&GameEvents::HitStop(0.1));
+        camera.update(0.05);
+        assert_eq!(camera.sim_delta(0.05), 0.0);
+        camera.update(0.06);
+        assert_eq!(camera.sim_delta(0.05), 0.05);
+
+        camera.enabled = false;
+        camera.handle_event(&GameEvents::HitStop(0.1));
+        assert_eq!(camera.sim_delta(0.05), 0.05);
+    }
+}
@@ -0,0 +1,245 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use macroquad::{miniquad::window::clipboard_set, prelude::*};
+
+use super::{i18n::Strings, save, sim::MicrogameResult, ui::draw_text_centered};
+
+const DAILY_FILE: &str = "daily.txt";
+
+/// A calendar day in UTC, so everyone gets the same daily whatever their timezone
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Date {
+    pub year: i32,
+    pub month: u8,
+    pub day: u8,
+}
+
+impl Date {
+    pub fn today() -> Date {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        Date::from_days((secs / 86_400) as i64)
+    }
+
+    /// `days` counts from 1970-01-01
+    pub fn from_days(days: i64) -> Date {
+        // Howard Hinnant's civil_from_days, with years starting in March
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+        let year = (yoe + era * 400 + (month <= 2) as i64) as i32;
+        Date { year, month, day }
+    }
+
+    /// ISO style, also the key the day's best is saved under
+    pub fn code(&self) -> String {
+        format!("{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+
+    /// Seed for the day's run, neighbouring days come out nowhere near each other
+    pub fn seed(&self) -> u64 {
+        // splitmix64 finalizer
+        let mut x = (self.year as u64) * 10_000 + self.month as u64 * 100 + self.day as u64;
+        x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        x ^ (x >> 31)
+    }
+}
+
+/// How a daily run went, one entry per microgame played
+#[derive(Debug, Clone, PartialEq)]
+pub struct DailyResult {
+    pub won: bool,
+    pub outcomes: Vec<bool>,
+}
+
+impl DailyResult {
+    pub fn from_history(won: bool, history: &[MicrogameResult]) -> Self {
+        DailyResult {
+            won,
+            outcomes: history.iter().map(|r| r.won_at.is_some()).collect(),
+        }
+    }
+
+    pub fn wins(&self) -> usize {
+        self.outcomes.iter().filter(|w| **w).count()
+    }
+
+    /// Finishing beats not finishing, then more wins, then fewer losses
+    pub fn beats(&self, other: &DailyResult) -> bool {
+        let key = |r: &DailyResult| (r.won, r.wins(), usize::MAX - r.outcomes.len());
+        key(self) > key(other)
+    }
+
+    fn encode(&self) -> String {
+        let outcomes: String = self
+            .outcomes
+            .iter()
+            .map(|w| if *w { 'W' } else { 'L' })
+            .collect();
+        format!("{} {outcomes}", if self.won { "won" } else { "lost" })
+    }
+
+    fn parse(text: &str) -> Option<DailyResult> {
+        let (won, outcomes) = text.split_once(' ').unwrap_or((text, ""));
+        Some(DailyResult {
+            won: match won {
+                "won" => true,
+                "lost" => false,
+                _ => return None,
+            },
+            outcomes: outcomes
+                .chars()
+                .map(|c| match c {
+                    'W' => Some(true),
+                    'L' => Some(false),
+                    _ => None,
+                })
+                .collect::<Option<_>>()?,
+        })
+    }
+
+    /// Spoiler free summary to paste elsewhere, one square per microgame
+    pub fn share_text(&self, date: Date, run_length: u8) -> String {
+        let squares: String = (0..run_length as usize)
+            .map(|i| match self.outcomes.get(i) {
+                Some(true) => '\u{1f7e9}',
+                Some(false) => '\u{1f7e5}',
+                None => '\u{2b1b}',
+            })
+            .collect();
+        format!(
+            "Microship daily {} {}/{}\n{squares}",
+            date.code(),
+            self.wins(),
+            run_length
+        )
+    }
+}
+
+pub fn load_best(date: Date) -> Option<DailyResult> {
+    save::read_kv(DAILY_FILE)
+        .get(&date.code())
+        .and_then(|t| DailyResult::parse(t))
+}
+
+/// Keeps `result` if it's the best of the day so far, returning whether it was
+pub fn save_if_best(date: Date, result: &DailyResult) -> bool {
+    if load_best(date).is_some_and(|best| !result.beats(&best)) {
+        return false;
+    }
+    let mut kv = save::read_kv(DAILY_FILE);
+    kv.insert(date.code(), result.encode());
+    let mut entries: Vec<(&str, String)> =
+        kv.iter().map(|(k, v)| (k.as_str(), v.clone())).collect();
+    entries.sort();
+    let _ = save::write_kv(DAILY_FILE, &entries);
+    true
+}
+
+/// What the end screens show after a daily run
+pub struct DailySummary {
+    pub date: Date,
+    pub result: DailyResult,
+    pub best: DailyResult,
+    pub new_best: bool,
+}
+
+impl DailySummary {
+    /// Saves the run if it's the day's best and puts the share text on the clipboard
+    pub fn record(date: Date, result: DailyResult, run_length: u8) -> Self {
+        let new_best = save_if_best(date, &result);
+        clipboard_set(&result.share_text(date, run_length));
+        DailySummary {
+            date,
+            best: load_best(date).unwrap_or_else(|| result.clone()),
+            result,
+            new_best,
+        }
+    }
+
+    /// Banner across the run summary, just above its buttons
+    pub fn draw(&self, strings: &Strings) {
+        draw_rectangle(0.0, 440.0, 960.0, 84.0, Color::new(0.0, 0.0, 0.0, 0.7));
+        let best = if self.new_best {
+            strings.get("daily.new_best").to_string()
+        } else {
+            format!("{} {}", strings.get("daily.best"), self.best.wins())
+        };
+        draw_text_centered(
+            &format!(
+                "{} {}  {} {}  {best}",
+                strings.get("mode.daily"),
+                self.date.code(),
+                strings.get("results.wins"),
+                self.result.wins()
+            ),
+            Vec2::new(480.0, 466.0),
+            36.0,
+            YELLOW,
+        );
+        draw_text_centered(
+            strings.get("daily.copied"),
+            Vec2::new(480.0, 502.0),
+            24.0,
+            WHITE,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(won: bool, outcomes: &str) -> DailyResult {
+        DailyResult::parse(&format!("{} {outcomes}", if won { "won" } else { "lost" })).unwrap()
+    }
+
+    #[test]
+    fn days_become_dates() {
+        assert_eq!(Date::from_days(0).code(), "1970-01-01");
+        assert_eq!(Date::from_days(59).code(), "1970-03-01");
+        assert_eq!(Date::from_days(11_016).code(), "2000-02-29");
+        assert_eq!(Date::from_days(20_745).code(), "2026-10-19");
+        assert_eq!(Date::from_days(-1).code(), "1969-12-31");
+    }
+
+    #[test]
+    fn each_day_gets_its_own_seed() {
+        let seeds: std::collections::HashSet<u64> = (20_000..20_400)
+            .map(|d| Date::from_days(d).seed())
+            .collect();
+        assert_eq!(seeds.len(), 400);
+        assert_eq!(Date::from_days(5).seed(), Date::from_days(5).seed());
+    }
+
+    #[test]
+    fn results_compare_and_round_trip() {
+        let finished = result(true, "WWLWW");
+        assert_eq!(
+            DailyResult::parse(&finished.encode()),
+            Some(finished.clone())
+        );
+        assert!(finished.beats(&result(false, "WWWWWWLLL")));
+        assert!(result(false, "WWWLL").beats(&result(false, "WWLL")));
+        assert!(result(false, "WWLL").beats(&result(false, "WWLLL")));
+        assert!(!finished.beats(&finished));
+        assert_eq!(DailyResult::parse("won WX"), None);
+    }
+
+    #[test]
+    fn share_text_pads_the_unplayed_stages() {
+        let text = result(false, "WLL").share_text(Date::from_days(0), 5);
+        assert_eq!(
+            text,
+            "Microship daily 1970-01-01 1/5\n\u{1f7e9}\u{1f7e5}\u{1f7e5}\u{2b1b}\u{2b1b}"
+        );
+    }
+}
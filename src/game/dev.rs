@@ -0,0 +1,347 @@
+use std::collections::VecDeque;
+
+use macroquad::{audio::play_sound_once, prelude::*};
+
+use super::{
+    gameplay::InGameData,
+    microgames::{id_from_name, name_of},
+    rng::Rng,
+    ActiveState, FrameInput, GameState,
+};
+
+const OVERLAY_KEY: KeyCode = KeyCode::F3;
+const CONSOLE_KEY: KeyCode = KeyCode::GraveAccent;
+const PAUSE_KEY: KeyCode = KeyCode::F5;
+const STEP_KEY: KeyCode = KeyCode::F6;
+const SLOWER_KEY: KeyCode = KeyCode::F7;
+const FASTER_KEY: KeyCode = KeyCode::F8;
+const LOG_LINES: usize = 8;
+
+/// Speeds the slower/faster hotkeys move between
+const TIME_SCALES: [f32; 7] = [0.1, 0.25, 0.5, 1.0, 1.5, 2.0, 4.0];
+const MIN_TIME_SCALE: f32 = 0.1;
+const MAX_TIME_SCALE: f32 = 4.0;
+/// Deltatime of a single frame step, before time scaling
+const STEP_TIME: f32 = 1.0 / 60.0;
+const HITBOX_COLOR: Color = Color::new(1.0, 0.0, 1.0, 0.9);
+
+const HELP: &str =
+    "win | lose | skip | goto <microgame> | lives <n> | seed <n> | timescale <x> | stats";
+
+/// Area a microgame reacts to, drawn by the dev overlay
+pub enum Hitbox {
+    Rect((Vec2, Vec2)),
+    Circle(Vec2, f32),
+}
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Win,
+    Lose,
+    Skip,
+    Goto(u8),
+    Lives(u8),
+    Seed(u64),
+    TimeScale(f32),
+    Stats,
+    Help,
+}
+
+fn parse_command(line: &str) -> Result<Command, String> {
+    let mut words = line.split_whitespace();
+    let name = words.next().ok_or("")?;
+    let arg = words.next();
+    let need_arg = || arg.ok_or(format!("{name} needs an argument"));
+    let bad_arg = |_| format!("bad argument for {name}");
+    match name {
+        "win" => Ok(Command::Win),
+        "lose" => Ok(Command::Lose),
+        "skip" => Ok(Command::Skip),
+        "goto" => {
+            let game = need_arg()?;
+            id_from_name(game)
+                .map(Command::Goto)
+                .ok_or(format!("no microgame called {game}"))
+        }
+        "lives" => match need_arg()?.parse().map_err(bad_arg)? {
+            0 => Err("lives has to be at least 1".to_string()),
+            n => Ok(Command::Lives(n)),
+        },
+        "seed" => Ok(Command::Seed(need_arg()?.parse().map_err(bad_arg)?)),
+        "timescale" => {
+            let scale: f32 = need_arg()?
+                .parse()
+                .map_err(|_| "bad argument for timescale")?;
+            if (MIN_TIME_SCALE..=MAX_TIME_SCALE).contains(&scale) {
+                Ok(Command::TimeScale(scale))
+            } else {
+                Err(format!(
+                    "timescale has to be between {MIN_TIME_SCALE} and {MAX_TIME_SCALE}"
+                ))
+            }
+        }
+        "stats" => Ok(Command::Stats),
+        "help" => Ok(Command::Help),
+        _ => Err(format!("unknown command {name}, try help")),
+    }
+}
+
+/// Debug overlay and console, only built with the `dev` feature
+pub struct DevTools {
+    overlay: bool,
+    console_open: bool,
+    line: String,
+    log: VecDeque<String>,
+    time_scale: f32,
+    paused: bool,
+}
+
+impl Default for DevTools {
+    fn default() -> Self {
+        DevTools {
+            overlay: false,
+            console_open: false,
+            line: String::new(),
+            log: VecDeque::new(),
+            time_scale: 1.0,
+            paused: false,
+        }
+    }
+}
+
+impl DevTools {
+    /// Next preset speed up or down from the current one
+    fn step_time_scale(&mut self, faster: bool) {
+        let scale = self.time_scale;
+        let next = if faster {
+            TIME_SCALES.iter().find(|s| **s > scale)
+        } else {
+            TIME_SCALES.iter().rev().find(|s| **s < scale)
+        };
+        if let Some(next) = next {
+            self.time_scale = *next;
+        }
+    }
+
+    fn print(&mut self, text: String) {
+        self.log.push_back(text);
+        while self.log.len() > LOG_LINES {
+            self.log.pop_front();
+        }
+    }
+}
+
+/// Handles the dev hotkeys and console, giving back the input and deltatime the
+/// game should see this frame, or None if it's paused
+pub fn update(
+    state: &mut GameState,
+    input: FrameInput,
+    deltatime: f32,
+) -> Option<(FrameInput, f32)> {
+    if is_key_pressed(OVERLAY_KEY) {
+        state.dev.overlay = !state.dev.overlay;
+    }
+    if is_key_pressed(CONSOLE_KEY) {
+        state.dev.console_open = !state.dev.console_open;
+    }
+
+    let input = if state.dev.console_open {
+        for c in input.typed.chars() {
+            match c {
+                '\u{8}' => {
+                    state.dev.line.pop();
+                }
+                '\n' => {
+                    let line = std::mem::take(&mut state.dev.line);
+                    state.dev.print(format!("> {line}"));
+                    match parse_command(&line).and_then(|c| run_command(state, c)) {
+                        Ok(Some(reply)) => state.dev.print(reply),
+                        Ok(None) => {}
+                        Err(e) => state.dev.print(e),
+                    }
+                }
+                '`' => {}
+                c => state.dev.line.push(c),
+            }
+        }
+        if is_key_pressed(KeyCode::Escape) {
+            state.dev.console_open = false;
+        }
+        // Typing shouldn't play the game
+        FrameInput {
+            mouse_position: input.mouse_position,
+            ..Default::default()
+        }
+    } else {
+        input
+    };
+
+    // Function keys never type into the console, so these work with it open
+    let dev = &mut state.dev;
+    if is_key_pressed(SLOWER_KEY) {
+        dev.step_time_scale(false);
+    }
+    if is_key_pressed(FASTER_KEY) {
+        dev.step_time_scale(true);
+    }
+    if is_key_pressed(PAUSE_KEY) {
+        dev.paused = !dev.paused;
+    }
+    if !dev.paused {
+        Some((input, deltatime * dev.time_scale))
+    } else if is_key_pressed(STEP_KEY) {
+        Some((input, STEP_TIME * dev.time_scale))
+    } else {
+        None
+    }
+}
+
+fn in_run(state: &mut GameState) -> Result<&mut InGameData, String> {
+    match &mut state.active_state {
+        ActiveState::InGame(data) => Ok(data),
+        _ => Err("not in a run".to_string()),
+    }
+}
+
+fn run_command(state: &mut GameState, command: Command) -> Result<Option<String>, String> {
+    match command {
+        Command::Win => in_run(state)?.dev_force_result(true)?,
+        Command::Lose => in_run(state)?.dev_force_result(false)?,
+        Command::Skip => in_run(state)?.dev_skip()?,
+        Command::Goto(id) => in_run(state)?.dev_goto(id),
+        Command::Lives(n) => in_run(state)?.dev_set_lives(n),
+        Command::Seed(seed) => {
+            if in_run(state).is_err() {
+                play_sound_once(&state.song);
+            }
+            let config = state.settings.mode.config();
+            let style = state.settings.style;
+            state.active_state =
+                ActiveState::InGame(InGameData::with_style(config, style, Rng::new(seed)));
+            return Ok(Some(format!("new run with seed {seed}")));
+        }
+        Command::TimeScale(scale) => state.dev.time_scale = scale,
+        Command::Stats => {
+            let counts: Vec<String> = state
+                .stats
+                .entries()
+                .iter()
+                .map(|(k, v)| format!("{k} {v}"))
+                .collect();
+            return Ok(Some(counts.join(", ")));
+        }
+        Command::Help => return Ok(Some(HELP.to_string())),
+    }
+    Ok(None)
+}
+
+/// Hitboxes, drawn under the game camera so they line up with the shaken scene
+pub fn draw_world(state: &GameState) {
+    if !state.dev.overlay {
+        return;
+    }
+    let ActiveState::InGame(data) = &state.active_state else {
+        return;
+    };
+    for hitbox in data.dev_current().hitboxes() {
+        match hitbox {
+            Hitbox::Rect((min, max)) => {
+                let size = max - min;
+                draw_rectangle_lines(min.x, min.y, size.x, size.y, 2.0, HITBOX_COLOR);
+            }
+            Hitbox::Circle(center, radius) => {
+                draw_circle_lines(center.x, center.y, radius, 2.0, HITBOX_COLOR);
+            }
+        }
+    }
+}
+
+/// Stats panel and console, drawn in screen space
+pub fn draw_overlay(state: &GameState) {
+    let dev = &state.dev;
+    if dev.paused || dev.time_scale != 1.0 {
+        let label = if dev.paused {
+            format!("PAUSED  x{}  F6 step", dev.time_scale)
+        } else {
+            format!("x{}", dev.time_scale)
+        };
+        draw_text(&label, 16.0, 92.0, 24.0, ORANGE);
+    }
+    if dev.overlay {
+        let (mx, my) = mouse_position();
+        let mut lines = vec![
+            format!(
+                "{} fps  {:.1} ms  x{}",
+                get_fps(),
+                get_frame_time() * 1000.0,
+                dev.time_scale
+            ),
+            format!("mouse {mx:.0}, {my:.0}"),
+        ];
+        if let ActiveState::InGame(data) = &state.active_state {
+            let (name, t) = data.dev_state();
+            lines.push(format!("{name} {t:.2}s  {}", data.dev_current().name()));
+            lines.push(format!("lives {}  seed {}", data.lives(), data.seed()));
+            let queue: Vec<&str> = data.dev_queue().map(name_of).collect();
+            lines.push(format!("queue {}", queue.join(" ")));
+        }
+        draw_rectangle(
+            8.0,
+            100.0,
+            420.0,
+            12.0 + 20.0 * lines.len() as f32,
+            Color::new(0.0, 0.0, 0.0, 0.6),
+        );
+        for (i, line) in lines.iter().enumerate() {
+            draw_text(line, 16.0, 120.0 + 20.0 * i as f32, 20.0, GREEN);
+        }
+    }
+
+    if dev.console_open {
+        let height = 24.0 + 20.0 * LOG_LINES as f32;
+        let top = 600.0 - height;
+        draw_rectangle(0.0, top, 960.0, height, Color::new(0.0, 0.0, 0.0, 0.8));
+        for (i, line) in dev.log.iter().enumerate() {
+            draw_text(line, 8.0, top + 18.0 + 20.0 * i as f32, 20.0, LIGHTGRAY);
+        }
+        draw_text(&format!("> {}_", dev.line), 8.0, 592.0, 20.0, WHITE);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_commands() {
+        assert_eq!(parse_command("win"), Ok(Command::Win));
+        assert_eq!(parse_command(" lives 5 "), Ok(Command::Lives(5)));
+        assert_eq!(parse_command("goto crank"), Ok(Command::Goto(6)));
+        assert_eq!(parse_command("seed 12345"), Ok(Command::Seed(12345)));
+        assert_eq!(parse_command("timescale 0.5"), Ok(Command::TimeScale(0.5)));
+    }
+
+    #[test]
+    fn time_scale_hotkeys_walk_the_presets() {
+        let mut dev = DevTools::default();
+        dev.step_time_scale(true);
+        assert_eq!(dev.time_scale, 1.5);
+        dev.time_scale = 0.3;
+        dev.step_time_scale(false);
+        assert_eq!(dev.time_scale, 0.25);
+        dev.time_scale = MAX_TIME_SCALE;
+        dev.step_time_scale(true);
+        assert_eq!(dev.time_scale, MAX_TIME_SCALE);
+    }
+
+    #[test]
+    fn rejects_bad_commands() {
+        assert!(parse_command("goto nowhere").is_err());
+        assert!(parse_command("lives").is_err());
+        assert!(parse_command("lives many").is_err());
+        assert!(parse_command("lives 0").is_err());
+        assert!(parse_command("timescale 0").is_err());
+        assert!(parse_command("timescale 8").is_err());
+        assert!(parse_command("dance").is_err());
+    }
+}
@@ -0,0 +1,441 @@
+use macroquad::prelude::*;
+
+use super::{
+    bot::BotAction,
+    camera::SCREEN_SIZE,
+    doors_closing, doors_opening, draw_doors,
+    gameplay::plate_drop,
+    microgames::{gen_microgame, MicrogameStatus, Microgames},
+    party::{PlayStyle, Players, Standing, PLAYER_COLORS},
+    rng::Rng,
+    run_config::RunConfig,
+    settings::Accessibility,
+    ui::draw_text_centered,
+    ActiveState, FrameInput, GameEvents, GameState, MousePressState,
+};
+
+const TRANS_IN_TIME: f32 = 1.0;
+const TRANS_OUT_TIME: f32 = 0.75;
+/// Pause on who took the round before the doors shut
+const RESULT_TIME: f32 = 1.0;
+const WIN_SCORE: u32 = 100;
+/// Second player's cursor, in game space
+const CURSOR_SPEED: f32 = 700.0;
+
+/// Microgames that work as a race, both players always get the same layout
+const DUEL_POOL: [u8; 5] = [0, 1, 3, 4, 6];
+
+/// Top left of each player's half, the full game space is drawn at `VIEW_SCALE`
+const VIEWS: [Vec2; 2] = [Vec2::new(0.0, 150.0), Vec2::new(480.0, 150.0)];
+const VIEW_SCALE: f32 = 0.5;
+
+#[derive(Debug, PartialEq)]
+enum RoundState {
+    TransIn(f32),
+    Playing(f32),
+    /// Somebody finished or the time ran out, showing who took it
+    Result(f32),
+    TransOut(f32),
+}
+
+/// Two players racing through the same microgame side by side
+pub struct DuelData {
+    round: u8,
+    config: RunConfig,
+    rng: Rng,
+    game_id: u8,
+    games: [Microgames; 2],
+    /// Seconds into the round each player finished at
+    finished: [Option<f32>; 2],
+    round_winner: Option<usize>,
+    state: RoundState,
+    players: Players,
+    p2_cursor: Vec2,
+}
+
+impl DuelData {
+    pub fn new(config: RunConfig, rng: Rng) -> Self {
+        let mut data = DuelData {
+            round: 0,
+            players: Players::new(PlayStyle::Duel, config.lives),
+            config,
+            rng,
+            game_id: 0,
+            games: [Microgames::Dummy, Microgames::Dummy],
+            finished: [None, None],
+            round_winner: None,
+            state: RoundState::TransIn(TRANS_IN_TIME),
+            p2_cursor: SCREEN_SIZE / 2.0,
+        };
+        data.start_round();
+        data
+    }
+
+    fn start_round(&mut self) {
+        let last = self.game_id;
+        let options: Vec<u8> = DUEL_POOL
+            .into_iter()
+            .filter(|id| self.round == 0 || *id != last)
+            .collect();
+        self.game_id = options[self.rng.gen_range(0, options.len())];
+        let seed = self.rng.gen_range(0, u32::MAX) as u64;
+        self.games = [
+            gen_microgame(self.game_id, &Rng::new(seed)),
+            gen_microgame(self.game_id, &Rng::new(seed)),
+        ];
+        self.finished = [None, None];
+        self.round_winner = None;
+        self.p2_cursor = SCREEN_SIZE / 2.0;
+    }
+
+    /// Quickest finisher takes the round, a dead heat goes to nobody
+    fn end_round(&mut self, events: &mut Vec<GameEvents>) {
+        self.round_winner = match self.finished {
+            [Some(a), Some(b)] if a == b => None,
+            [Some(a), Some(b)] => Some(if a < b { 0 } else { 1 }),
+            [Some(_), None] => Some(0),
+            [None, Some(_)] => Some(1),
+            [None, None] => None,
+        };
+        if let Some(winner) = self.round_winner {
+            let time = self.finished[winner].unwrap_or(0.0);
+            let player = self.players.player_mut(winner);
+            player.score += WIN_SCORE;
+            player.wins += 1;
+            player.win_time += time;
+            events.push(GameEvents::ScreenShake(0.3));
+        }
+    }
+
+    /// Advances both players by a frame, `inputs` already in game space
+    pub fn step(
+        &mut self,
+        inputs: [FrameInput; 2],
+        delta: f32,
+        access: &Accessibility,
+        events: &mut Vec<GameEvents>,
+    ) {
+        let time_limit = self.config.time_for(self.round) * access.timer_multiplier;
+        let state_time = match &mut self.state {
+            RoundState::TransIn(t)
+            | RoundState::Playing(t)
+            | RoundState::Result(t)
+            | RoundState::TransOut(t) => {
+                *t -= delta;
+                *t
+            }
+        };
+
+        let playing = matches!(self.state, RoundState::Playing(_));
+        let mut all_lost = true;
+        for (i, input) in inputs.into_iter().enumerate() {
+            let input = if playing {
+                input.with_assist(access)
+            } else {
+                FrameInput::default()
+            };
+            let status = self.games[i].update(input, delta, events);
+            all_lost &= status == MicrogameStatus::Lost;
+            if playing && status.is_won() && self.finished[i].is_none() {
+                self.finished[i] = Some(time_limit - state_time.max(0.0));
+            }
+        }
+
+        // Nobody can win it any more, so there's no point waiting out the timer
+        if playing && (all_lost || self.finished.iter().any(Option::is_some)) {
+            self.end_round(events);
+            self.state = RoundState::Result(RESULT_TIME);
+        } else if state_time <= 0.0 {
+            self.state = match self.state {
+                RoundState::TransIn(_) => RoundState::Playing(time_limit),
+                RoundState::Playing(_) => {
+                    self.end_round(events);
+                    RoundState::Result(RESULT_TIME)
+                }
+                RoundState::Result(_) => RoundState::TransOut(TRANS_OUT_TIME),
+                RoundState::TransOut(_) => {
+                    // Doors just slammed shut
+                    events.push(GameEvents::ScreenShake(0.3));
+                    self.round += 1;
+                    if self.round >= self.config.length {
+                        events.push(GameEvents::PartyOver);
+                    } else {
+                        self.start_round();
+                    }
+                    RoundState::TransIn(TRANS_IN_TIME)
+                }
+            }
+        }
+    }
+
+    /// What a scripted `player` would do this frame, idle outside rounds
+    pub fn bot_action(&self, player: usize) -> BotAction {
+        if matches!(self.state, RoundState::Playing(_)) {
+            self.games[player].bot_action()
+        } else {
+            BotAction::Idle
+        }
+    }
+
+    pub fn standings(&self) -> Vec<Standing> {
+        self.players.standings()
+    }
+}
+
+/// Maps a point on screen into player `view`'s game space
+fn to_game_space(view: usize, pos: Vec2) -> Vec2 {
+    (pos - VIEWS[view]) / VIEW_SCALE
+}
+
+pub fn update(
+    game_data: &mut GameState,
+    input: FrameInput,
+    delta: f32,
+    events: &mut Vec<GameEvents>,
+) -> Result<(), ()> {
+    if let ActiveState::Duel(d_data) = &mut game_data.active_state {
+        let p1 = FrameInput {
+            mouse_position: to_game_space(0, input.mouse_position),
+            mouse_state: input.mouse_state,
+            key_held: input.key_held,
+            ..Default::default()
+        };
+
+        let pad = input.p2;
+        d_data.p2_cursor = (d_data.p2_cursor + pad.dir.normalize_or_zero() * CURSOR_SPEED * delta)
+            .clamp(Vec2::ZERO, SCREEN_SIZE);
+        let p2 = FrameInput {
+            mouse_position: d_data.p2_cursor,
+            mouse_state: if pad.pressed {
+                MousePressState::JustPressed
+            } else if pad.held {
+                MousePressState::Pressed
+            } else {
+                MousePressState::NotPressed
+            },
+            ..Default::default()
+        };
+
+        d_data.step([p1, p2], delta, &game_data.settings.access, events);
+        Ok(())
+    } else {
+        Err(())
+    }
+}
+
+/// Camera drawing the full game space into one of the split-screen targets
+fn view_camera(target: &RenderTarget) -> Camera2D {
+    let mut camera = Camera2D::from_display_rect(Rect::new(0.0, 0.0, SCREEN_SIZE.x, SCREEN_SIZE.y));
+    camera.render_target = Some(target.clone());
+    // Render targets come out upside down otherwise
+    camera.zoom.y = -camera.zoom.y;
+    camera
+}
+
+pub fn draw(game_data: &GameState) -> Result<(), ()> {
+    if let ActiveState::Duel(d_data) = &game_data.active_state {
+        let access = &game_data.settings.access;
+        let strings = &game_data.strings;
+
+        for (game, target) in d_data.games.iter().zip(&game_data.split_screen) {
+            set_camera(&view_camera(target));
+            clear_background(BLACK);
+            game.draw(&game_data.textures, access);
+        }
+        set_camera(&game_data.camera_fx.camera());
+
+        clear_background(Color::new(0.08, 0.08, 0.12, 1.0));
+        let view_size = SCREEN_SIZE * VIEW_SCALE;
+        let time_limit = d_data.config.time_for(d_data.round) * access.timer_multiplier;
+        for (i, target) in game_data.split_screen.iter().enumerate() {
+            let corner = VIEWS[i];
+            let color = PLAYER_COLORS[i];
+            draw_texture_ex(
+                &target.texture,
+                corner.x,
+                corner.y,
+                WHITE,
+                DrawTextureParams {
+                    dest_size: Some(view_size),
+                    ..Default::default()
+                },
+            );
+            draw_rectangle_lines(corner.x, corner.y, view_size.x, view_size.y, 6.0, color);
+
+            let wins = d_data.players.player(i).wins;
+            draw_text_centered(
+                &format!("{} {}  {wins}", strings.get("hud.player"), i + 1),
+                Vec2::new(corner.x + view_size.x / 2.0, corner.y - 30.0),
+                40.0,
+                color,
+            );
+
+            if let RoundState::Playing(t) = d_data.state {
+                let fraction = if d_data.finished[i].is_some() {
+                    0.0
+                } else {
+                    t / time_limit
+                };
+                draw_rectangle(
+                    corner.x + 10.0,
+                    corner.y + view_size.y + 16.0,
+                    (view_size.x - 20.0) * fraction,
+                    10.0,
+                    YELLOW,
+                );
+            }
+        }
+
+        // First player uses the real mouse, the second gets a drawn cursor
+        let cursor = VIEWS[1] + d_data.p2_cursor * VIEW_SCALE;
+        draw_circle_lines(cursor.x, cursor.y, 10.0, 3.0, PLAYER_COLORS[1]);
+        draw_circle(cursor.x, cursor.y, 3.0, PLAYER_COLORS[1]);
+
+        draw_text_centered(
+            &format!(
+                "{}/{}",
+                (d_data.round + 1).min(d_data.config.length),
+                d_data.config.length
+            ),
+            Vec2::new(480.0, 40.0),
+            40.0,
+            WHITE,
+        );
+
+        match d_data.state {
+            RoundState::TransIn(t) => {
+                let elapsed = TRANS_IN_TIME - t;
+                draw_doors(
+                    &game_data.textures,
+                    doors_opening(TRANS_IN_TIME).sample(elapsed),
+                );
+                let plate = game_data.textures.get(d_data.games[0].plate_key()).unwrap();
+                draw_texture(
+                    plate,
+                    280.0,
+                    plate_drop(TRANS_IN_TIME).sample(elapsed),
+                    WHITE,
+                );
+            }
+            RoundState::Result(_) => {
+                let text = match d_data.round_winner {
+                    Some(i) => format!(
+                        "{} {} {}",
+                        strings.get("hud.player"),
+                        i + 1,
+                        strings.get("duel.wins")
+                    ),
+                    None => strings.get("duel.draw").to_string(),
+                };
+                let color = d_data.round_winner.map_or(WHITE, |i| PLAYER_COLORS[i]);
+                draw_rectangle(180.0, 250.0, 600.0, 100.0, Color::new(0.0, 0.0, 0.0, 0.8));
+                draw_text_centered(&text, Vec2::new(480.0, 300.0), 56.0, color);
+            }
+            RoundState::TransOut(t) => {
+                let elapsed = TRANS_OUT_TIME - t;
+                draw_doors(
+                    &game_data.textures,
+                    doors_closing(0.7).with_delay(0.05).sample(elapsed),
+                );
+            }
+            RoundState::Playing(_) => {}
+        }
+
+        Ok(())
+    } else {
+        Err(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{
+        bot::{Bot, BotSkill},
+        run_config::GameMode,
+    };
+
+    const FRAME: f32 = 1.0 / 60.0;
+
+    /// Plays a whole duel with an optional bot on each side
+    fn play_out(duel: &mut DuelData, skills: [Option<BotSkill>; 2]) {
+        let mut bots = skills.map(|s| s.map(|s| Bot::new(s, 0)));
+        let access = Accessibility::default();
+        let mut events = vec![];
+        while !events.contains(&GameEvents::PartyOver) {
+            events.clear();
+            let inputs = [0, 1].map(|i| match &mut bots[i] {
+                Some(bot) => bot.input(duel.bot_action(i), FRAME),
+                None => FrameInput::default(),
+            });
+            duel.step(inputs, FRAME, &access, &mut events);
+        }
+    }
+
+    #[test]
+    fn first_to_finish_takes_the_round() {
+        for seed in 0..5 {
+            let mut duel = DuelData::new(GameMode::Short.config(), Rng::new(seed));
+            play_out(&mut duel, [None, Some(BotSkill::PERFECT)]);
+            let standings = duel.standings();
+            assert_eq!(standings[0].number, 2);
+            assert_eq!(standings[0].player.wins, GameMode::Short.config().length);
+            assert_eq!(standings[1].player.wins, 0);
+        }
+    }
+
+    #[test]
+    fn dead_heats_go_to_nobody() {
+        let mut duel = DuelData::new(GameMode::Short.config(), Rng::new(1));
+        play_out(
+            &mut duel,
+            [Some(BotSkill::PERFECT), Some(BotSkill::PERFECT)],
+        );
+        assert!(duel.standings().iter().all(|s| s.player.wins == 0));
+    }
+
+    #[test]
+    fn drag_assist_works_in_duels() {
+        let holding = || FrameInput {
+            key_held: true,
+            ..Default::default()
+        };
+        for assist in [false, true] {
+            let mut duel = DuelData::new(GameMode::Short.config(), Rng::new(2));
+            // Crank and sweep are the drag microgames in the pool
+            for id in [3, 6] {
+                duel.games = [0, 1].map(|_| gen_microgame(id, &Rng::new(0)));
+                duel.state = RoundState::Playing(5.0);
+                let access = Accessibility {
+                    drag_assist: assist,
+                    ..Default::default()
+                };
+                let mut events = vec![];
+                while matches!(duel.state, RoundState::Playing(_)) {
+                    duel.step(
+                        [holding(), FrameInput::default()],
+                        FRAME,
+                        &access,
+                        &mut events,
+                    );
+                }
+                assert_eq!(duel.round_winner, assist.then_some(0), "game {id}");
+            }
+        }
+    }
+
+    #[test]
+    fn both_sides_get_the_same_layout() {
+        let duel = DuelData::new(GameMode::Standard.config(), Rng::new(9));
+        assert_eq!(duel.games[0].bot_action(), duel.games[1].bot_action());
+    }
+
+    #[test]
+    fn screen_points_map_into_each_half() {
+        assert_eq!(to_game_space(1, VIEWS[1]), Vec2::ZERO);
+        assert_eq!(
+            to_game_space(0, VIEWS[0] + SCREEN_SIZE * VIEW_SCALE),
+            SCREEN_SIZE
+        );
+    }
+}
@@ -0,0 +1,153 @@
+use super::{save, GameEvents};
+
+const STATS_FILE: &str = "stats.txt";
+
+/// Anything that reacts to game events, so whatever raises them doesn't need to know it exists
+pub trait Listener {
+    fn handle_event(&mut self, event: &GameEvents);
+}
+
+/// Hands every event to every listener, in the order given
+pub fn dispatch(events: &[GameEvents], listeners: &mut [&mut dyn Listener]) {
+    for event in events {
+        for listener in listeners.iter_mut() {
+            listener.handle_event(event);
+        }
+    }
+}
+
+/// Lifetime counters built up from the event stream
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Stats {
+    pub runs_started: u32,
+    pub microgames_played: u32,
+    pub microgames_won: u32,
+    pub lives_lost: u32,
+    pub buttons_pressed: u32,
+    pub wrong_inputs: u32,
+    pub lasers_fired: u32,
+    pub cans_swapped: u32,
+}
+
+impl Stats {
+    pub fn load() -> Self {
+        let kv = save::read_kv(STATS_FILE);
+        let get = |key: &str| kv.get(key).and_then(|v| v.parse().ok()).unwrap_or(0);
+        Stats {
+            runs_started: get("runs_started"),
+            microgames_played: get("microgames_played"),
+            microgames_won: get("microgames_won"),
+            lives_lost: get("lives_lost"),
+            buttons_pressed: get("buttons_pressed"),
+            wrong_inputs: get("wrong_inputs"),
+            lasers_fired: get("lasers_fired"),
+            cans_swapped: get("cans_swapped"),
+        }
+    }
+
+    /// Every counter with the key it's saved under
+    pub fn entries(&self) -> [(&'static str, u32); 8] {
+        [
+            ("runs_started", self.runs_started),
+            ("microgames_played", self.microgames_played),
+            ("microgames_won", self.microgames_won),
+            ("lives_lost", self.lives_lost),
+            ("buttons_pressed", self.buttons_pressed),
+            ("wrong_inputs", self.wrong_inputs),
+            ("lasers_fired", self.lasers_fired),
+            ("cans_swapped", self.cans_swapped),
+        ]
+    }
+
+    fn save(&self) {
+        let entries: Vec<(&str, String)> = self
+            .entries()
+            .iter()
+            .map(|(k, v)| (*k, v.to_string()))
+            .collect();
+        let _ = save::write_kv(STATS_FILE, &entries);
+    }
+
+    /// Counts the event, returning whether it's a good moment to save
+    pub fn record(&mut self, event: &GameEvents) -> bool {
+        match event {
+            GameEvents::StartGameplay
+            | GameEvents::StartOnline { .. }
+            | GameEvents::RetryRun { .. } => self.runs_started += 1,
+            GameEvents::MicrogameEnded { won_at, .. } => {
+                self.microgames_played += 1;
+                self.microgames_won += won_at.is_some() as u32;
+                return true;
+            }
+            GameEvents::LifeLost { .. } => self.lives_lost += 1,
+            GameEvents::ButtonPressed => self.buttons_pressed += 1,
+            GameEvents::WrongInput => self.wrong_inputs += 1,
+            GameEvents::LaserFired { .. } => self.lasers_fired += 1,
+            GameEvents::CanSwapped => self.cans_swapped += 1,
+            _ => {}
+        }
+        false
+    }
+}
+
+impl Listener for Stats {
+    fn handle_event(&mut self, event: &GameEvents) {
+        // Once per microgame is plenty, the odd lost press on quitting doesn't matter
+        if self.record(event) {
+            self.save();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Log(Vec<String>);
+
+    impl Listener for Log {
+        fn handle_event(&mut self, event: &GameEvents) {
+            self.0.push(format!("{event:?}"));
+        }
+    }
+
+    #[test]
+    fn every_listener_sees_every_event() {
+        let (mut a, mut b) = (Log::default(), Log::default());
+        dispatch(
+            &[GameEvents::ButtonPressed, GameEvents::CanSwapped],
+            &mut [&mut a, &mut b],
+        );
+        assert_eq!(a.0, ["ButtonPressed", "CanSwapped"]);
+        assert_eq!(a.0, b.0);
+    }
+
+    #[test]
+    fn stats_count_and_save_after_microgames() {
+        let mut stats = Stats::default();
+        assert!(!stats.record(&GameEvents::StartGameplay));
+        assert!(!stats.record(&GameEvents::LaserFired {
+            hit: false,
+            first_frame: false
+        }));
+        assert!(!stats.record(&GameEvents::LifeLost { lives_left: 2 }));
+        assert!(stats.record(&GameEvents::MicrogameEnded {
+            name: "asteroids",
+            boss: false,
+            stage: 0,
+            time_limit: 5.0,
+            won_at: None,
+        }));
+        assert_eq!(
+            stats,
+            Stats {
+                runs_started: 1,
+                microgames_played: 1,
+                lives_lost: 1,
+                lasers_fired: 1,
+                ..Default::default()
+            }
+        );
+    }
+}
@@ -1,19 +1,47 @@
 use crate::game::ActiveState;
 
 use super::{
-    microgames::{
-        always_win, asteroids, combo, course, crank, gen_microgame, gen_microgame_queue,
-        gen_new_microgame, imposter, pipes, swap, sweep, Microgames,
-    },
-    particles_draw, particles_update, FrameInput, GameEvents, GameState, MousePressState,
+    bot::BotAction,
+    doors_closing, doors_opening, draw_doors,
+    ghost::Ghost,
+    history::Replay,
+    hud::{self, draw_prompt, Hud, HudInfo},
+    microgames::{gen_microgame, MicrogameStatus, Microgames},
+    party::{PlayStyle, Players, Standing, PLAYER_COLORS},
+    queue::gen_microgame_queue,
+    rng::Rng,
+    run_config::{GameMode, RunConfig},
+    score,
+    settings::Accessibility,
+    sim::MicrogameResult,
+    tween::{Ease, Lerp, Tween},
+    ui::draw_text_centered,
+    FrameInput, GameEvents, GameState, MousePressState,
 };
 use macroquad::prelude::*;
 
+const TRANS_IN_TIME: f32 = 1.0;
+/// Bosses get a longer intro so their plate can sink in
+const BOSS_TRANS_IN_TIME: f32 = 2.0;
+/// Long enough to read whose turn it is
+const TURN_TRANS_IN_TIME: f32 = 1.5;
+const TRANS_OUT_TIME: f32 = 0.75;
+const PHASE_BREAK_TIME: f32 = hud::PHASE_TIME;
+/// Time left on the timer once a microgame's outcome is settled, so the run doesn't wait out the rest
+const OUTRO_TIME: f32 = 1.0;
+/// Beating a boss won't give a life past this many
+const MAX_LIVES: u8 = 5;
+
 #[derive(Debug, PartialEq)]
 enum MicrogameState {
     TransOut(f32),
     TransIn(f32),
     InMicrogame(f32),
+    /// Pause between boss phases, keeping the microgame time to carry on with
+    PhaseBreak {
+        left: f32,
+        time_left: f32,
+    },
 }
 
 enum EndPlateState {
@@ -24,219 +52,537 @@ enum EndPlateState {
 
 impl MicrogameState {
     fn is_in_microgame(&self) -> bool {
-        match self {
-            MicrogameState::InMicrogame(_) => true,
-            _ => false,
-        }
+        matches!(self, MicrogameState::InMicrogame(_))
     }
 }
 
 pub struct InGameData {
     microgames_completed: u8,
-    lives: u8,
+    players: Players,
     current_microgame_win: bool,
+    /// Seconds into the current microgame it was won at, cleared if it's lost again
+    won_at: Option<f32>,
     microgame_state: MicrogameState,
+    /// Seconds played of the current microgame, which unlike its timer doesn't skip
+    /// ahead once it's settled
+    microgame_clock: f32,
     current_microgame: Microgames,
+    current_id: u8,
+    /// Seeds the current microgame, so versus players all get the same layout
+    stage_seed: u64,
+    /// Phase the current boss was last seen in
+    phase: u8,
     game_queue: Vec<u8>,
+    /// Every microgame played so far, in order
+    history: Vec<MicrogameResult>,
     end_plate: EndPlateState,
+    hud: Hud,
+    config: RunConfig,
+    rng: Rng,
+    /// Every input of the run so far, started on the first frame with the settings in use
+    recording: Option<Replay>,
+    /// Past run being watched instead of played, with the next frame to feed in
+    playback: Option<(Replay, usize)>,
+    /// Cursor of the best earlier attempt at the same seed, drawn over each microgame
+    ghost: Option<Ghost>,
+    /// Result forced from the dev console, applied when the microgame ends
+    #[cfg(feature = "dev")]
+    forced_result: Option<bool>,
 }
 
 impl Default for InGameData {
     fn default() -> Self {
-        let mut game_queue = gen_microgame_queue();
-        InGameData {
+        InGameData::new(GameMode::Standard.config(), Rng::from_global())
+    }
+}
+
+impl InGameData {
+    pub fn new(config: RunConfig, rng: Rng) -> Self {
+        InGameData::with_style(config, PlayStyle::Solo, rng)
+    }
+
+    pub fn with_style(config: RunConfig, style: PlayStyle, rng: Rng) -> Self {
+        let mut game_queue = gen_microgame_queue(&config, &rng);
+        let first = game_queue.pop().unwrap();
+        let mut data = InGameData {
             end_plate: EndPlateState::None,
             microgames_completed: 0,
-            lives: 3,
+            players: Players::new(style, config.lives),
             current_microgame_win: false,
-            microgame_state: MicrogameState::TransIn(1.0),
-            current_microgame: gen_microgame(game_queue.pop().unwrap()),
+            won_at: None,
+            microgame_state: MicrogameState::TransIn(0.0),
+            microgame_clock: 0.0,
+            current_microgame: Microgames::Dummy,
+            current_id: first,
+            stage_seed: 0,
+            phase: 0,
             game_queue,
+            history: vec![],
+            config,
+            rng,
+            recording: None,
+            playback: None,
+            ghost: None,
+            #[cfg(feature = "dev")]
+            forced_result: None,
+            hud: Hud::default(),
+        };
+        data.start_microgame(first);
+        data
+    }
+
+    /// Plays `replay` back from the start of the run it was recorded on
+    pub fn replaying(config: RunConfig, rng: Rng, replay: Replay) -> Self {
+        InGameData {
+            playback: Some((replay, 0)),
+            ..InGameData::new(config, rng)
         }
     }
-}
 
-pub fn update(
-    game_data: &mut GameState,
-    input: FrameInput,
-    delta: f32,
-    events: &mut Vec<GameEvents>,
-) -> Result<(), ()> {
-    if let ActiveState::InGame(gs_data) = &mut game_data.active_state {
-        let state_time = match &mut gs_data.microgame_state {
-            MicrogameState::TransIn(t) => {
-                *t -= delta;
-                *t
+    /// Swaps in the recorded input when watching a replay, otherwise records the player's.
+    /// None once the replay has nothing left to play
+    pub fn next_input(
+        &mut self,
+        input: FrameInput,
+        delta: f32,
+        access: &Accessibility,
+    ) -> Option<(FrameInput, f32)> {
+        match &mut self.playback {
+            Some((replay, next)) => {
+                *next += 1;
+                replay.frame(*next - 1)
             }
-            MicrogameState::TransOut(t) => {
-                *t -= delta;
-                *t
+            None => {
+                self.recording
+                    .get_or_insert_with(|| Replay::new(access))
+                    .record(&input, delta);
+                Some((input, delta))
             }
-            MicrogameState::InMicrogame(t) => {
+        }
+    }
+
+    /// Settings the run plays under, a replay keeps the timers it was recorded with
+    fn access(&self, live: &Accessibility) -> Accessibility {
+        match &self.playback {
+            Some((replay, _)) => replay.access(live),
+            None => *live,
+        }
+    }
+
+    pub fn with_ghost(self, ghost: Option<Ghost>) -> Self {
+        InGameData { ghost, ..self }
+    }
+
+    pub fn is_in_microgame(&self) -> bool {
+        self.microgame_state.is_in_microgame()
+    }
+
+    pub fn microgame_clock(&self) -> f32 {
+        self.microgame_clock
+    }
+
+    pub fn is_replay(&self) -> bool {
+        self.playback.is_some()
+    }
+
+    pub fn recording(&self) -> Option<&Replay> {
+        self.recording.as_ref()
+    }
+
+    /// Brings in a freshly seeded microgame, starting from the doors opening
+    fn start_microgame(&mut self, game_id: u8) {
+        self.current_id = game_id;
+        self.stage_seed = self.rng.gen_range(0, u32::MAX) as u64;
+        self.restart_microgame();
+    }
+
+    /// Brings the current microgame back exactly as it started, for the next versus player
+    fn restart_microgame(&mut self) {
+        self.current_microgame = gen_microgame(self.current_id, &Rng::new(self.stage_seed));
+        self.microgame_state = MicrogameState::TransIn(self.trans_in_time());
+    }
+
+    fn trans_in_time(&self) -> f32 {
+        if self.current_microgame.is_boss() {
+            BOSS_TRANS_IN_TIME
+        } else if self.players.is_multiplayer() {
+            TURN_TRANS_IN_TIME
+        } else {
+            TRANS_IN_TIME
+        }
+    }
+
+    /// Advances the run by one frame, independent of menus, audio and drawing
+    pub fn step(
+        &mut self,
+        input: FrameInput,
+        delta: f32,
+        access: &Accessibility,
+        events: &mut Vec<GameEvents>,
+    ) {
+        self.hud.update(delta);
+
+        let state_time = match &mut self.microgame_state {
+            MicrogameState::TransIn(t)
+            | MicrogameState::TransOut(t)
+            | MicrogameState::InMicrogame(t)
+            | MicrogameState::PhaseBreak { left: t, .. } => {
                 *t -= delta;
                 *t
             }
         };
 
-        let filtered_input: FrameInput = if gs_data.microgame_state.is_in_microgame() {
-            input
+        let filtered_input: FrameInput = if self.microgame_state.is_in_microgame() {
+            self.microgame_clock += delta;
+            input.with_assist(access)
         } else {
-            FrameInput {
-                mouse_position: Vec2::ZERO,
-                mouse_state: MousePressState::NotPressed,
-            }
+            FrameInput::default()
         };
 
-        let microgame_won = match &mut gs_data.current_microgame {
-            Microgames::AlwaysWin(d) => always_win::update(d, filtered_input, delta),
-            Microgames::Pipes(d) => pipes::update(d, filtered_input, delta),
-            Microgames::Combo(d) => combo::update(d, filtered_input, delta),
-            Microgames::Course(d) => course::update(d, filtered_input, delta),
-            Microgames::Sweep(d) => sweep::update(d, filtered_input, delta),
-            Microgames::Asteroids(d) => asteroids::update(d, filtered_input, delta),
-            Microgames::Imposter(d) => imposter::update(d, filtered_input, delta),
-            Microgames::Swap(d) => swap::update(d, filtered_input, delta),
-            Microgames::Crank(d) => crank::update(d, filtered_input, delta),
-            _ => true,
-        };
+        let status = self.current_microgame.update(filtered_input, delta, events);
+        #[cfg(feature = "dev")]
+        let status = self
+            .forced_result
+            .map_or(status, MicrogameStatus::won_while);
+        let microgame_won = status.is_won();
+
+        let time_limit = self.time_limit(access);
+        if let MicrogameState::InMicrogame(t) = self.microgame_state {
+            if !microgame_won {
+                self.won_at = None;
+            } else if self.won_at.is_none() {
+                self.won_at = Some(time_limit - t.max(0.0));
+            }
+
+            let phase = self.current_microgame.phase();
+            if status.is_decided() {
+                self.microgame_state = MicrogameState::InMicrogame(t.min(OUTRO_TIME));
+            } else if phase > self.phase {
+                self.hud.announce_phase(phase + 1);
+                events.push(GameEvents::ScreenShake(0.4));
+                self.microgame_state = MicrogameState::PhaseBreak {
+                    left: PHASE_BREAK_TIME,
+                    time_left: t,
+                };
+            }
+            self.phase = phase;
+        }
 
         if state_time <= 0.0 {
-            gs_data.microgame_state = match gs_data.microgame_state {
+            self.microgame_state = match self.microgame_state {
                 MicrogameState::TransIn(_) => {
-                    if gs_data.microgames_completed > 9 {
-                        MicrogameState::InMicrogame(3.0)
-                    } else if gs_data.microgames_completed > 4 {
-                        MicrogameState::InMicrogame(4.0)
-                    } else {
-                        MicrogameState::InMicrogame(5.0)
+                    self.won_at = None;
+                    #[cfg(feature = "dev")]
+                    {
+                        self.forced_result = None;
                     }
+                    self.phase = 0;
+                    self.microgame_clock = 0.0;
+                    events.push(GameEvents::MicrogameStarted {
+                        name: self.current_microgame.name(),
+                        boss: self.current_microgame.is_boss(),
+                        stage: self.microgames_completed,
+                        time_limit,
+                    });
+                    MicrogameState::InMicrogame(time_limit)
+                }
+                MicrogameState::PhaseBreak { time_left, .. } => {
+                    MicrogameState::InMicrogame(time_left)
                 }
                 MicrogameState::InMicrogame(_) => {
+                    let boss = self.current_microgame.is_boss();
+                    let player = self.players.current_mut();
                     if !microgame_won {
-                        gs_data.lives -= 1;
-                        gs_data.end_plate = EndPlateState::LostMG(1);
+                        player.lives -= 1;
+                        player.lives_lost += 1;
+                        player.streak = 0;
+                        self.hud.break_heart(player.lives);
+                        events.push(GameEvents::LifeLost {
+                            lives_left: player.lives,
+                        });
+                        self.end_plate = EndPlateState::LostMG(1);
+                        events.push(GameEvents::ScreenShake(0.5));
+                        events.push(GameEvents::ZoomPunch(0.1));
                     } else {
-                        gs_data.end_plate = EndPlateState::WonMG(1);
+                        self.end_plate = EndPlateState::WonMG(1);
+                        let won_at = self.won_at.unwrap_or(0.0);
+                        let points = score::win_points(time_limit, won_at, boss, player.streak);
+                        player.score += points;
+                        player.streak += 1;
+                        player.wins += 1;
+                        player.win_time += won_at;
+                        self.hud.add_points(points);
+                        if boss && player.lives < MAX_LIVES {
+                            player.lives += 1;
+                            self.hud.gain_heart(player.lives - 1);
+                        }
+                    }
+                    let result = MicrogameResult {
+                        name: self.current_microgame.name(),
+                        boss,
+                        stage: self.microgames_completed,
+                        time_limit,
+                        won_at: self.won_at,
+                    };
+                    events.push(GameEvents::MicrogameEnded {
+                        name: result.name,
+                        boss,
+                        stage: result.stage,
+                        time_limit,
+                        won_at: result.won_at,
+                    });
+                    self.history.push(result);
+                    if self.players.last_in_stage() {
+                        self.microgames_completed += 1;
                     }
-                    gs_data.microgames_completed += 1;
-                    MicrogameState::TransOut(0.75)
+                    MicrogameState::TransOut(TRANS_OUT_TIME)
                 }
                 MicrogameState::TransOut(_) => {
-                    gs_data.current_microgame = gen_microgame(gs_data.game_queue.pop().unwrap());
-                    if gs_data.microgames_completed == 15 && gs_data.lives > 0 {
-                        events.push(GameEvents::GameWon);
-                    }
-                    if gs_data.lives <= 0 {
-                        events.push(GameEvents::GameLost);
+                    // Doors just slammed shut
+                    events.push(GameEvents::ScreenShake(0.3));
+                    let completed = self.microgames_completed;
+                    let all_out = self.players.all_out();
+                    if all_out || completed >= self.config.length {
+                        for player in self.players.iter_mut() {
+                            if !player.is_out() && player.lives_lost == 0 {
+                                player.score += score::NO_DAMAGE_BONUS;
+                            }
+                        }
+                        events.push(if self.players.is_multiplayer() {
+                            GameEvents::PartyOver
+                        } else if all_out {
+                            GameEvents::GameLost
+                        } else {
+                            GameEvents::GameWon
+                        });
+                    } else if !self.players.advance() {
+                        self.restart_microgame();
+                    } else if let Some(next) = self.game_queue.pop() {
+                        self.start_microgame(next);
+                        if self.config.time_for(completed) < self.config.time_for(completed - 1) {
+                            self.hud.announce_speed_up();
+                        }
                     }
-                    MicrogameState::TransIn(1.0)
+                    MicrogameState::TransIn(self.trans_in_time())
                 }
             }
         }
+    }
 
-        return Ok(());
-    } else {
-        return Err(());
+    /// Full timer for the current microgame
+    fn time_limit(&self, access: &Accessibility) -> f32 {
+        let time = self.current_microgame.fixed_time();
+        time.unwrap_or_else(|| self.config.time_for(self.microgames_completed))
+            * access.timer_multiplier
+    }
+
+    /// What a scripted player would do this frame, idle between microgames
+    pub fn bot_action(&self) -> BotAction {
+        if self.microgame_state.is_in_microgame() {
+            self.current_microgame.bot_action()
+        } else {
+            BotAction::Idle
+        }
+    }
+
+    /// Lives of whoever's playing right now
+    pub fn lives(&self) -> u8 {
+        self.players.current().lives
+    }
+
+    /// Score of whoever's playing right now
+    pub fn score(&self) -> u32 {
+        self.players.current().score
+    }
+
+    pub fn history(&self) -> &[MicrogameResult] {
+        &self.history
+    }
+
+    pub fn standings(&self) -> Vec<Standing> {
+        self.players.standings()
+    }
+
+    pub fn style(&self) -> PlayStyle {
+        self.players.style()
+    }
+
+    pub fn config(&self) -> &RunConfig {
+        &self.config
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.rng.seed()
     }
 }
 
-fn lerp(f: f32, t: f32, d: f32) -> f32 {
-    t * d + f * (1.0 - d)
+/// Hooks for the dev overlay and console
+#[cfg(feature = "dev")]
+impl InGameData {
+    /// Current state name and the time left in it
+    pub fn dev_state(&self) -> (&'static str, f32) {
+        match self.microgame_state {
+            MicrogameState::TransIn(t) => ("TransIn", t),
+            MicrogameState::InMicrogame(t) => ("InMicrogame", t),
+            MicrogameState::TransOut(t) => ("TransOut", t),
+            MicrogameState::PhaseBreak { left, .. } => ("PhaseBreak", left),
+        }
+    }
+
+    pub fn dev_current(&self) -> &Microgames {
+        &self.current_microgame
+    }
+
+    /// Upcoming microgame ids, next one first
+    pub fn dev_queue(&self) -> impl Iterator<Item = u8> + '_ {
+        self.game_queue.iter().rev().copied()
+    }
+
+    /// Ends the running microgame right away with the given result
+    pub fn dev_force_result(&mut self, won: bool) -> Result<(), String> {
+        if !self.microgame_state.is_in_microgame() {
+            return Err("not in a microgame".to_string());
+        }
+        self.forced_result = Some(won);
+        if won && self.won_at.is_none() {
+            self.won_at = Some(0.0);
+        }
+        self.microgame_state = MicrogameState::InMicrogame(0.0);
+        Ok(())
+    }
+
+    /// Throws away the current microgame and brings in the next one from the queue
+    pub fn dev_skip(&mut self) -> Result<(), String> {
+        let next = self.game_queue.pop().ok_or("queue is empty")?;
+        self.dev_goto(next);
+        Ok(())
+    }
+
+    /// Swaps in a fresh copy of the given microgame, starting from the doors opening
+    pub fn dev_goto(&mut self, game_id: u8) {
+        self.start_microgame(game_id);
+        self.forced_result = None;
+    }
+
+    pub fn dev_set_lives(&mut self, lives: u8) {
+        self.players.current_mut().lives = lives;
+    }
 }
 
-// Scuffed ass function for easing towards the middle
-fn anti_easing(f: f32) -> f32 {
-    if f > 0.5 {
-        ((f - 0.5) * 1.4).powf(2.0) + 0.5
+pub fn update(
+    game_data: &mut GameState,
+    input: FrameInput,
+    delta: f32,
+    events: &mut Vec<GameEvents>,
+) -> Result<(), ()> {
+    if let ActiveState::InGame(gs_data) = &mut game_data.active_state {
+        let access = gs_data.access(&game_data.settings.access);
+        // Clicking leaves a replay, whatever it was doing
+        let stopped = gs_data.is_replay() && input.mouse_state == MousePressState::JustPressed;
+        match gs_data.next_input(input, delta, &access) {
+            Some((input, delta)) if !stopped => gs_data.step(input, delta, &access, events),
+            _ => events.push(GameEvents::OpenHistory),
+        }
+        if let Some(ghost) = &mut gs_data.ghost {
+            ghost.catch_up(gs_data.history.len());
+        }
+        Ok(())
     } else {
-        0.5 - ((0.5 - f) * 1.4).powf(2.0)
+        Err(())
     }
 }
 
+/// Microgame plate dropping past the open doors
+pub fn plate_drop(duration: f32) -> Tween<f32> {
+    Tween::new(-300.0, 700.0, duration, Ease::OutInQuad)
+}
+
+/// Good/bad plate dropping past the closing doors
+fn end_plate_drop() -> Tween<f32> {
+    Tween::new(-150.0, 600.0, 0.7, Ease::OutInQuad).with_delay(0.05)
+}
+
+/// Regular plate framed in flashing red under a boss banner
+fn draw_boss_plate(plate: &Texture2D, y: f32, text: &str, elapsed: f32) {
+    let flash = (elapsed * 12.0).sin() * 0.5 + 0.5;
+    let frame = RED.lerp(ORANGE, flash);
+    draw_rectangle(
+        264.0,
+        y - 86.0,
+        plate.width() + 32.0,
+        plate.height() + 102.0,
+        frame,
+    );
+    draw_texture(plate, 280.0, y, WHITE);
+    draw_text_centered(
+        text,
+        Vec2::new(280.0 + plate.width() / 2.0, y - 40.0),
+        64.0,
+        WHITE,
+    );
+}
+
+/// Whose turn it is, hanging under the microgame plate
+fn draw_turn_plate(plate: &Texture2D, y: f32, text: &str, color: Color) {
+    let top = y + plate.height() + 12.0;
+    draw_rectangle(280.0, top, plate.width(), 64.0, color);
+    draw_rectangle_lines(280.0, top, plate.width(), 64.0, 6.0, WHITE);
+    draw_text_centered(
+        text,
+        Vec2::new(280.0 + plate.width() / 2.0, top + 32.0),
+        56.0,
+        WHITE,
+    );
+}
+
 pub fn draw(game_data: &GameState) -> Result<(), ()> {
     if let ActiveState::InGame(gs_data) = &game_data.active_state {
-        match &gs_data.current_microgame {
-            Microgames::AlwaysWin(d) => always_win::draw(d),
-            Microgames::Pipes(d) => pipes::draw(d, &game_data.textures),
-            Microgames::Combo(d) => combo::draw(d, &game_data.textures),
-            Microgames::Course(d) => course::draw(d, &game_data.textures),
-            Microgames::Sweep(d) => sweep::draw(d, &game_data.textures),
-            Microgames::Asteroids(d) => asteroids::draw(d, &game_data.textures),
-            Microgames::Imposter(d) => imposter::draw(d, &game_data.textures),
-            Microgames::Swap(d) => swap::draw(d, &game_data.textures),
-            Microgames::Crank(d) => crank::draw(d, &game_data.textures),
-            _ => {}
+        let access = &gs_data.access(&game_data.settings.access);
+        let time_limit = gs_data.time_limit(access);
+        gs_data.current_microgame.draw(&game_data.textures, access);
+        if let (Some(ghost), true) = (&gs_data.ghost, gs_data.is_in_microgame()) {
+            ghost.draw(gs_data.history.len(), gs_data.microgame_clock);
         }
 
-        // Particles
-        let maxtime: f32 = if gs_data.microgames_completed > 9 {
-            3.0
-        } else if gs_data.microgames_completed > 4 {
-            4.0
-        } else {
-            5.0
-        };
-
         // Draw UI
-        if let MicrogameState::InMicrogame(t) = gs_data.microgame_state {
-            draw_rectangle(30.0, 560.0, 900.0 * (t / maxtime), 10.0, YELLOW);
-        } else {
-            let r_door = game_data.textures.get("right_door").unwrap();
-            let l_door = game_data.textures.get("left_door").unwrap();
-
+        if !gs_data.microgame_state.is_in_microgame() {
             match gs_data.microgame_state {
                 MicrogameState::TransIn(t) => {
-                    let raw_progress = clamp((1.0 - t), 0.0, 1.0);
-                    draw_texture(
-                        l_door,
-                        lerp(0.0, -500.0, raw_progress * raw_progress),
-                        0.0,
-                        WHITE,
-                    );
-                    draw_texture(
-                        r_door,
-                        lerp(462.0, 1000.0, raw_progress * raw_progress),
-                        0.0,
-                        WHITE,
-                    );
-                    let mg_plate = match gs_data.current_microgame {
-                        Microgames::Swap(_) => "swap_plate",
-                        Microgames::Pipes(_) => "pipes_plate",
-                        Microgames::Combo(_) => "combo_plate",
-                        Microgames::Sweep(_) => "sweep_plate",
-                        Microgames::Crank(_) => "crank_plate",
-                        Microgames::Course(_) => "course_plate",
-                        Microgames::Imposter(_) => "imposter_plate",
-                        Microgames::Asteroids(_) => "asteroids_plate",
-                        _ => "",
-                    };
-                    draw_texture(
-                        game_data.textures.get(mg_plate).unwrap(),
-                        280.0,
-                        -300.0 + 1000.0 * anti_easing(raw_progress),
-                        WHITE,
-                    );
+                    let duration = gs_data.trans_in_time();
+                    let elapsed = duration - t;
+                    draw_doors(&game_data.textures, doors_opening(duration).sample(elapsed));
+                    let plate = game_data
+                        .textures
+                        .get(gs_data.current_microgame.plate_key())
+                        .unwrap();
+                    let y = plate_drop(duration).sample(elapsed);
+                    if gs_data.current_microgame.is_boss() {
+                        draw_boss_plate(plate, y, game_data.strings.get("hud.boss"), elapsed);
+                    } else {
+                        draw_texture(plate, 280.0, y, WHITE);
+                    }
+                    if gs_data.players.is_multiplayer() {
+                        let number = gs_data.players.current_number();
+                        draw_turn_plate(
+                            plate,
+                            y,
+                            &format!("{} {number}", game_data.strings.get("hud.player")),
+                            PLAYER_COLORS[number as usize - 1],
+                        );
+                    }
                 }
                 MicrogameState::TransOut(t) => {
-                    let raw_progress = clamp((0.7 - t) * (1.0 / 0.7), 0.0, 1.0);
-                    draw_texture(
-                        l_door,
-                        lerp(-500.0, 0.0, raw_progress * raw_progress),
-                        0.0,
-                        WHITE,
-                    );
-                    draw_texture(
-                        r_door,
-                        lerp(1000.0, 462.0, raw_progress * raw_progress),
-                        0.0,
-                        WHITE,
+                    let elapsed = TRANS_OUT_TIME - t;
+                    draw_doors(
+                        &game_data.textures,
+                        doors_closing(0.7).with_delay(0.05).sample(elapsed),
                     );
                     match gs_data.end_plate {
                         EndPlateState::WonMG(s) => {
                             draw_texture(
                                 game_data.textures.get("good_1").unwrap(),
                                 380.0,
-                                -150.0 + 750.0 * anti_easing(raw_progress),
+                                end_plate_drop().sample(elapsed),
                                 WHITE,
                             );
                         }
@@ -244,7 +590,7 @@ pub fn draw(game_data: &GameState) -> Result<(), ()> {
                             draw_texture(
                                 game_data.textures.get("bad_1").unwrap(),
                                 380.0,
-                                -150.0 + 750.0 * anti_easing(raw_progress),
+                                end_plate_drop().sample(elapsed),
                                 WHITE,
                             );
                         }
@@ -255,13 +601,298 @@ pub fn draw(game_data: &GameState) -> Result<(), ()> {
             };
         }
 
-        let heart = game_data.textures.get("heart").unwrap();
-        for i in 0..gs_data.lives {
-            draw_texture(heart, 16.0 + 80.0 * i as f32, 16.0, WHITE);
+        let timer = match gs_data.microgame_state {
+            MicrogameState::InMicrogame(t) => Some(t / time_limit),
+            MicrogameState::PhaseBreak { time_left, .. } => Some(time_left / time_limit),
+            _ => None,
+        };
+        gs_data.hud.draw(
+            &HudInfo {
+                lives: gs_data.players.current().lives,
+                stage: gs_data.microgames_completed + 1,
+                run_length: gs_data.config.length,
+                score: gs_data.players.current().score,
+                multiplier: score::multiplier(gs_data.players.current().streak),
+                player: gs_data
+                    .players
+                    .is_multiplayer()
+                    .then(|| gs_data.players.current_number()),
+                timer,
+            },
+            &game_data.textures,
+            &game_data.strings,
+        );
+
+        if let MicrogameState::InMicrogame(t) = gs_data.microgame_state {
+            let elapsed = time_limit - t;
+            draw_prompt(
+                game_data
+                    .strings
+                    .get(gs_data.current_microgame.prompt_key()),
+                elapsed,
+            );
         }
 
-        return Ok(());
+        if gs_data.is_replay() {
+            draw_text(
+                game_data.strings.get("history.replay"),
+                20.0,
+                580.0,
+                32.0,
+                SKYBLUE,
+            );
+            draw_text(
+                game_data.strings.get("history.stop"),
+                160.0,
+                580.0,
+                20.0,
+                LIGHTGRAY,
+            );
+        }
+
+        Ok(())
     } else {
-        return Err(());
+        Err(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{
+        bot::{Bot, BotSkill},
+        run_config::BossSchedule,
+    };
+
+    const FRAME: f32 = 1.0 / 60.0;
+
+    /// A run that's nothing but the given boss
+    fn boss_run(boss: u8) -> InGameData {
+        let config = RunConfig {
+            length: 1,
+            lives: 2,
+            bosses: vec![boss],
+            boss_schedule: BossSchedule::Every(1),
+            ..GameMode::Standard.config()
+        };
+        InGameData::new(config, Rng::new(0))
+    }
+
+    #[test]
+    fn phase_breaks_hold_the_timer() {
+        let mut run = boss_run(9);
+        let mut bot = Bot::new(BotSkill::PERFECT, 0);
+        let access = Accessibility::default();
+        let mut events = vec![];
+        while !matches!(run.microgame_state, MicrogameState::PhaseBreak { .. }) {
+            run.step(
+                bot.input(run.bot_action(), FRAME),
+                FRAME,
+                &access,
+                &mut events,
+            );
+        }
+        let MicrogameState::PhaseBreak { time_left, .. } = run.microgame_state else {
+            unreachable!()
+        };
+        assert_eq!(run.bot_action(), BotAction::Idle);
+        for _ in 0..(PHASE_BREAK_TIME / FRAME) as usize + 2 {
+            run.step(FrameInput::default(), FRAME, &access, &mut events);
+        }
+        match run.microgame_state {
+            MicrogameState::InMicrogame(t) => assert!(t > time_left - 2.0 * FRAME),
+            _ => panic!("still not back in the boss"),
+        }
+    }
+
+    /// Plays a run to the end, giving back every microgame's name and whether it was won
+    fn play_out(run: &mut InGameData, skill: Option<BotSkill>) -> Vec<(&'static str, bool)> {
+        let mut bot = skill.map(|s| Bot::new(s, 0));
+        let access = Accessibility::default();
+        let mut events = vec![];
+        let mut played = vec![];
+        loop {
+            let input = match &mut bot {
+                Some(bot) => bot.input(run.bot_action(), FRAME),
+                None => FrameInput::default(),
+            };
+            run.step(input, FRAME, &access, &mut events);
+            for event in events.drain(..) {
+                match event {
+                    GameEvents::MicrogameEnded { name, won_at, .. } => {
+                        played.push((name, won_at.is_some()))
+                    }
+                    GameEvents::PartyOver | GameEvents::GameWon | GameEvents::GameLost => {
+                        return played
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn replays_play_out_the_same_run() {
+        let config = RunConfig {
+            length: 6,
+            ..GameMode::Standard.config()
+        };
+        let sloppy = BotSkill {
+            cursor_speed: 900.0,
+            reaction_delay: 0.3,
+            miss_rate: 0.3,
+        };
+        let mut bot = Bot::new(sloppy, 3);
+        let mut run = InGameData::new(config.clone(), Rng::new(11));
+        let access = Accessibility::default();
+        let mut events = vec![];
+        let mut frame = 0;
+        while !events.contains(&GameEvents::GameWon) && !events.contains(&GameEvents::GameLost) {
+            // Uneven frame times, like a real machine
+            let delta = if frame % 3 == 0 { FRAME * 1.5 } else { FRAME };
+            let input = bot.input(run.bot_action(), delta);
+            let (input, delta) = run.next_input(input, delta, &access).unwrap();
+            run.step(input, delta, &access, &mut events);
+            frame += 1;
+        }
+
+        let replay = run.recording().unwrap().clone();
+        assert_eq!(replay.len(), frame);
+        let mut watched = InGameData::replaying(config, Rng::new(11), replay);
+        while let Some((input, delta)) = watched.next_input(FrameInput::default(), 0.5, &access) {
+            watched.step(input, delta, &access, &mut events);
+        }
+        assert_eq!(watched.history(), run.history());
+        assert_eq!(watched.score(), run.score());
+        assert!(watched.recording().is_none());
+    }
+
+    #[test]
+    fn versus_players_get_the_same_microgames() {
+        let config = RunConfig {
+            length: 4,
+            ..GameMode::Standard.config()
+        };
+        let mut run = InGameData::with_style(config, PlayStyle::Versus(3), Rng::new(5));
+        let played = play_out(&mut run, Some(BotSkill::PERFECT));
+        assert_eq!(played.len(), 12);
+        for stage in played.chunks(3) {
+            assert!(stage.iter().all(|p| p.0 == stage[0].0 && p.1));
+        }
+        let standings = run.standings();
+        assert!(standings.iter().all(|s| s.rank == 1 && s.player.wins == 4));
+    }
+
+    #[test]
+    fn microgames_report_their_start_and_end() {
+        let mut run = InGameData::new(GameMode::Standard.config(), Rng::new(3));
+        let access = Accessibility::default();
+        let mut events = vec![];
+        while !events
+            .iter()
+            .any(|e| matches!(e, GameEvents::MicrogameEnded { .. }))
+        {
+            run.step(FrameInput::default(), FRAME, &access, &mut events);
+        }
+        let flow: Vec<&GameEvents> = events
+            .iter()
+            .filter(|e| {
+                matches!(
+                    e,
+                    GameEvents::MicrogameStarted { .. }
+                        | GameEvents::LifeLost { .. }
+                        | GameEvents::MicrogameEnded { .. }
+                )
+            })
+            .collect();
+        assert!(matches!(
+            flow[..],
+            [
+                GameEvents::MicrogameStarted { stage: 0, .. },
+                GameEvents::LifeLost { lives_left: 2 },
+                GameEvents::MicrogameEnded { won_at: None, .. },
+            ]
+        ));
+    }
+
+    #[test]
+    fn settled_microgames_skip_the_rest_of_the_timer() {
+        let access = Accessibility::default();
+        // Pipes stay fixed once patched, an always-win game could in theory be undone
+        for (game_id, settles) in [(0, true), (10, false)] {
+            let mut run = InGameData::new(GameMode::Standard.config(), Rng::new(1));
+            let mut bot = Bot::new(BotSkill::PERFECT, 0);
+            let mut events = vec![];
+            while !run.microgame_state.is_in_microgame() {
+                run.step(FrameInput::default(), FRAME, &access, &mut events);
+            }
+            run.current_microgame = gen_microgame(game_id, &Rng::new(0));
+            let mut played = 0.0;
+            while run.microgame_state.is_in_microgame() {
+                run.step(
+                    bot.input(run.bot_action(), FRAME),
+                    FRAME,
+                    &access,
+                    &mut events,
+                );
+                played += FRAME;
+            }
+            let won_at = run.won_at.unwrap();
+            let limit = run.time_limit(&access);
+            if settles {
+                assert!(played <= won_at + OUTRO_TIME + 2.0 * FRAME);
+            } else {
+                assert!(played >= limit - 2.0 * FRAME);
+            }
+        }
+    }
+
+    #[test]
+    fn clean_runs_get_the_no_damage_bonus() {
+        let config = RunConfig {
+            length: 3,
+            ..GameMode::Standard.config()
+        };
+        let mut run = InGameData::new(config, Rng::new(4));
+        play_out(&mut run, Some(BotSkill::PERFECT));
+        assert_eq!(run.players.current().streak, 3);
+        // Each win pays at least its base points, times the streak so far
+        assert!(run.score() >= score::NO_DAMAGE_BONUS + 100 + 125 + 150);
+
+        let mut run = InGameData::new(GameMode::Standard.config(), Rng::new(4));
+        play_out(&mut run, None);
+        assert_eq!(run.score(), 0);
+    }
+
+    #[test]
+    fn party_ends_once_everyone_is_out() {
+        let mut run = InGameData::with_style(
+            GameMode::Standard.config(),
+            PlayStyle::Party(2),
+            Rng::new(2),
+        );
+        // Nobody touches anything, so every microgame is lost
+        let played = play_out(&mut run, None);
+        assert_eq!(played.len(), 6);
+        assert!(run.standings().iter().all(|s| s.player.is_out()));
+    }
+
+    #[test]
+    fn beating_a_boss_gives_a_life() {
+        for boss in [8, 9] {
+            let mut run = boss_run(boss);
+            let mut bot = Bot::new(BotSkill::PERFECT, 0);
+            let access = Accessibility::default();
+            let mut events = vec![];
+            while !events.contains(&GameEvents::GameWon) {
+                run.step(
+                    bot.input(run.bot_action(), FRAME),
+                    FRAME,
+                    &access,
+                    &mut events,
+                );
+            }
+            assert_eq!(run.lives(), 3);
+        }
     }
 }
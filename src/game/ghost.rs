@@ -0,0 +1,159 @@
+use macroquad::prelude::*;
+
+use super::{
+    gameplay::InGameData, history::Replay, rng::Rng, run_config::RunConfig,
+    settings::Accessibility, FrameInput, GameEvents, MousePressState,
+};
+
+const GHOST_COLOR: Color = Color::new(0.6, 0.9, 1.0, 0.45);
+/// How long the ghost takes to fade once its microgame was over
+const FADE_TIME: f32 = 0.3;
+
+/// Where the cursor was `elapsed` seconds into a microgame, and whether it was held down
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct GhostFrame {
+    elapsed: f32,
+    mouse: Vec2,
+    pressed: bool,
+}
+
+/// A past attempt's cursor, split up by microgame so it lines up with a retry
+/// however long the doors and plates in between took
+pub struct Ghost {
+    /// The old attempt being played back off screen, None once it's over
+    run: Option<Box<InGameData>>,
+    access: Accessibility,
+    /// One per microgame played so far, in order
+    microgames: Vec<Vec<GhostFrame>>,
+}
+
+impl Ghost {
+    pub fn new(config: RunConfig, seed: u64, replay: Replay) -> Self {
+        Ghost {
+            access: replay.access(&Accessibility::default()),
+            run: Some(Box::new(InGameData::replaying(
+                config,
+                Rng::new(seed),
+                replay,
+            ))),
+            microgames: vec![],
+        }
+    }
+
+    /// Plays the old attempt on until the `index`th microgame's cursor is all in.
+    /// Called every frame, it only does work once per microgame, while the doors
+    /// of the live run are still shut
+    pub fn catch_up(&mut self, index: usize) {
+        let mut events = vec![];
+        while self.microgames.len() <= index + 1 {
+            let Some(run) = &mut self.run else {
+                return;
+            };
+            let Some((input, delta)) = run.next_input(FrameInput::default(), 0.0, &self.access)
+            else {
+                self.run = None;
+                return;
+            };
+            // Only frames that ran the microgame, phase breaks hold it
+            let counting = run.is_in_microgame();
+            let pressed = input.mouse_state != MousePressState::NotPressed || input.key_held;
+            let mouse = input.mouse_position;
+            run.step(input, delta, &self.access, &mut events);
+            if let (true, Some(current)) = (counting, self.microgames.last_mut()) {
+                current.push(GhostFrame {
+                    elapsed: run.microgame_clock(),
+                    mouse,
+                    pressed,
+                });
+            }
+            for event in events.drain(..) {
+                if let GameEvents::MicrogameStarted { .. } = event {
+                    self.microgames.push(vec![]);
+                }
+            }
+        }
+    }
+
+    /// The ghost `elapsed` seconds into the `index`th microgame of the run, fading
+    /// out once it's past where the old attempt got to
+    fn at(&self, index: usize, elapsed: f32) -> Option<(GhostFrame, f32)> {
+        let frames = self.microgames.get(index)?;
+        let last = frames.last()?;
+        let alpha = 1.0 - ((elapsed - last.elapsed) / FADE_TIME).clamp(0.0, 1.0);
+        let i = frames.partition_point(|f| f.elapsed <= elapsed);
+        (alpha > 0.0).then(|| (frames[i.saturating_sub(1)], alpha))
+    }
+
+    pub fn draw(&self, index: usize, elapsed: f32) {
+        let Some((frame, alpha)) = self.at(index, elapsed) else {
+            return;
+        };
+        let color = Color {
+            a: GHOST_COLOR.a * alpha,
+            ..GHOST_COLOR
+        };
+        let Vec2 { x, y } = frame.mouse;
+        draw_circle_lines(x, y, 10.0, 3.0, color);
+        if frame.pressed {
+            draw_circle(x, y, 7.0, color);
+        } else {
+            draw_circle(x, y, 3.0, color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{
+        bot::{Bot, BotSkill},
+        run_config::GameMode,
+    };
+
+    #[test]
+    fn ghost_lines_up_with_each_microgame() {
+        let config = RunConfig {
+            length: 3,
+            ..GameMode::Standard.config()
+        };
+        let mut bot = Bot::new(BotSkill::PERFECT, 0);
+        let mut run = InGameData::new(config.clone(), Rng::new(5));
+        let access = Accessibility::default();
+        let mut events = vec![];
+        let (mut starts, mut over) = (0, false);
+        while !over {
+            let input = bot.input(run.bot_action(), 1.0 / 60.0);
+            let (input, delta) = run.next_input(input, 1.0 / 60.0, &access).unwrap();
+            run.step(input, delta, &access, &mut events);
+            for event in events.drain(..) {
+                match event {
+                    GameEvents::MicrogameStarted { .. } => starts += 1,
+                    GameEvents::GameWon | GameEvents::GameLost => over = true,
+                    _ => {}
+                }
+            }
+        }
+
+        let mut ghost = Ghost::new(config, 5, run.recording().unwrap().clone());
+        // Only as far as the live run needs, the first microgame and the start of the next
+        ghost.catch_up(0);
+        assert_eq!(ghost.microgames.len(), 2);
+        assert!(ghost.run.is_some());
+
+        ghost.catch_up(starts);
+        assert!(ghost.run.is_none());
+        assert_eq!(ghost.microgames.len(), starts);
+        for (frames, result) in ghost.microgames.iter().zip(run.history()) {
+            // The ghost runs out about when the old attempt's microgame ended,
+            // its clock going up a frame at a time even after it was settled
+            let last = frames.last().unwrap().elapsed;
+            assert!(last <= result.time_limit + 0.02);
+            assert!(frames
+                .windows(2)
+                .all(|w| (w[1].elapsed - w[0].elapsed - 1.0 / 60.0).abs() < 1e-4));
+        }
+        assert!(ghost.at(0, 0.5).is_some());
+        assert!(ghost.at(starts, 0.5).is_none());
+        assert!(ghost.at(0, 100.0).is_none());
+    }
+}
@@ -0,0 +1,579 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use macroquad::prelude::*;
+
+use super::{
+    daily::Date,
+    microgames::{id_from_name, name_of},
+    run_config::GameMode,
+    save,
+    settings::Accessibility,
+    sim::MicrogameResult,
+    ui::{draw_text_centered, Button},
+    ActiveState, FrameInput, GameEvents, GameState, MousePressState,
+};
+
+const HISTORY_FILE: &str = "history.txt";
+/// Runs kept, the oldest is dropped once there's more
+pub const MAX_RUNS: usize = 10;
+const ROW_HEIGHT: f32 = 34.0;
+const ROWS_TOP: f32 = 100.0;
+const WATCH_BUTTON: Button = Button::new(150.0, 536.0, 200.0, 48.0);
+const RETRY_BUTTON: Button = Button::new(380.0, 536.0, 200.0, 48.0);
+const BACK_BUTTON: Button = Button::new(610.0, 536.0, 200.0, 48.0);
+
+/// One gameplay step of a recorded run
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ReplayFrame {
+    delta: f32,
+    mouse: Vec2,
+    mouse_state: u8,
+    key_held: bool,
+}
+
+impl ReplayFrame {
+    fn new(input: &FrameInput, delta: f32) -> Self {
+        ReplayFrame {
+            delta,
+            mouse: input.mouse_position,
+            mouse_state: match input.mouse_state {
+                MousePressState::NotPressed => 0,
+                MousePressState::JustPressed => 1,
+                MousePressState::Pressed => 2,
+            },
+            key_held: input.key_held,
+        }
+    }
+
+    fn input(&self) -> FrameInput {
+        FrameInput {
+            mouse_position: self.mouse,
+            mouse_state: match self.mouse_state {
+                1 => MousePressState::JustPressed,
+                2 => MousePressState::Pressed,
+                _ => MousePressState::NotPressed,
+            },
+            key_held: self.key_held,
+            ..Default::default()
+        }
+    }
+
+    /// Floats print the shortest text that reads back the same, so playback stays exact
+    fn encode(&self) -> String {
+        format!(
+            "{} {} {} {} {}",
+            self.delta, self.mouse.x, self.mouse.y, self.mouse_state, self.key_held as u8
+        )
+    }
+
+    /// Gives back the frame and how many times in a row it was played
+    fn parse(line: &str) -> Option<(ReplayFrame, usize)> {
+        let mut parts = line.split_whitespace();
+        let mut next = || parts.next();
+        let frame = ReplayFrame {
+            delta: next()?.parse().ok()?,
+            mouse: Vec2::new(next()?.parse().ok()?, next()?.parse().ok()?),
+            mouse_state: next()?.parse().ok()?,
+            key_held: next()? == "1",
+        };
+        let repeat = next().map_or(Some(1), |n| n.parse().ok())?;
+        Some((frame, repeat))
+    }
+}
+
+/// Every input of a run, enough to play it back exactly as it went
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Replay {
+    /// Accessibility settings the run was played with, they change the timers
+    timer_multiplier: f32,
+    drag_assist: bool,
+    frames: Vec<ReplayFrame>,
+}
+
+impl Replay {
+    pub fn new(access: &Accessibility) -> Self {
+        Replay {
+            timer_multiplier: access.timer_multiplier,
+            drag_assist: access.drag_assist,
+            frames: vec![],
+        }
+    }
+
+    pub fn record(&mut self, input: &FrameInput, delta: f32) {
+        self.frames.push(ReplayFrame::new(input, delta));
+    }
+
+    /// Input and frame time of the `index`th step, None past the end
+    pub fn frame(&self, index: usize) -> Option<(FrameInput, f32)> {
+        self.frames.get(index).map(|f| (f.input(), f.delta))
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// The player's own settings, with the timing ones the run was recorded under
+    pub fn access(&self, live: &Accessibility) -> Accessibility {
+        Accessibility {
+            timer_multiplier: self.timer_multiplier,
+            drag_assist: self.drag_assist,
+            ..*live
+        }
+    }
+
+    fn encode(&self) -> String {
+        let mut text = format!(
+            "replay {} {}\n",
+            self.timer_multiplier, self.drag_assist as u8
+        );
+        // Runs of the same frame go on one line, the mouse sits still a lot
+        let mut i = 0;
+        while i < self.frames.len() {
+            let frame = self.frames[i];
+            let repeat = self.frames[i..].iter().take_while(|f| **f == frame).count();
+            text += &frame.encode();
+            if repeat > 1 {
+                text += &format!(" {repeat}");
+            }
+            text.push('\n');
+            i += repeat;
+        }
+        text
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunRecord {
+    pub mode: GameMode,
+    pub seed: u64,
+    pub won: bool,
+    pub score: u32,
+    /// Seconds since the unix epoch when the run ended
+    pub finished: u64,
+    pub results: Vec<MicrogameResult>,
+    pub replay: Option<Replay>,
+}
+
+impl RunRecord {
+    /// A run that ended just now
+    pub fn new(
+        mode: GameMode,
+        seed: u64,
+        won: bool,
+        score: u32,
+        results: Vec<MicrogameResult>,
+        replay: Option<Replay>,
+    ) -> Self {
+        let finished = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        RunRecord {
+            mode,
+            seed,
+            won,
+            score,
+            finished,
+            results,
+            replay,
+        }
+    }
+
+    pub fn wins(&self) -> usize {
+        self.results.iter().filter(|r| r.won_at.is_some()).count()
+    }
+
+    fn encode(&self) -> String {
+        let outcome = if self.won { "won" } else { "lost" };
+        let mut text = format!(
+            "run {} {} {outcome} {} {}\n",
+            self.mode.code(),
+            self.seed,
+            self.score,
+            self.finished
+        );
+        for r in &self.results {
+            let won_at = r.won_at.map_or("-".to_string(), |t| t.to_string());
+            text += &format!(
+                "game {} {} {} {} {won_at}\n",
+                r.name, r.boss as u8, r.stage, r.time_limit
+            );
+        }
+        if let Some(replay) = &self.replay {
+            text += &replay.encode();
+        }
+        text
+    }
+}
+
+fn parse_run(line: &str) -> Option<RunRecord> {
+    let mut parts = line.split_whitespace().skip(1);
+    let mut next = || parts.next();
+    Some(RunRecord {
+        mode: GameMode::from_code(next()?)?,
+        seed: next()?.parse().ok()?,
+        won: next()? == "won",
+        score: next()?.parse().ok()?,
+        finished: next()?.parse().ok()?,
+        results: vec![],
+        replay: None,
+    })
+}
+
+fn parse_result(line: &str) -> Option<MicrogameResult> {
+    let mut parts = line.split_whitespace().skip(1);
+    let mut next = || parts.next();
+    Some(MicrogameResult {
+        name: id_from_name(next()?).map(name_of)?,
+        boss: next()? == "1",
+        stage: next()?.parse().ok()?,
+        time_limit: next()?.parse().ok()?,
+        won_at: match next()? {
+            "-" => None,
+            t => Some(t.parse().ok()?),
+        },
+    })
+}
+
+fn parse_replay(line: &str) -> Option<Replay> {
+    let mut parts = line.split_whitespace().skip(1);
+    Some(Replay {
+        timer_multiplier: parts.next()?.parse().ok()?,
+        drag_assist: parts.next()? == "1",
+        frames: vec![],
+    })
+}
+
+/// The last few solo runs, newest first
+#[derive(Debug, Default)]
+pub struct RunHistory {
+    runs: Vec<RunRecord>,
+}
+
+impl RunHistory {
+    pub fn load() -> Self {
+        save::read_file(HISTORY_FILE)
+            .map(|t| RunHistory::parse(&t))
+            .unwrap_or_default()
+    }
+
+    /// A run whose lines don't all read back is dropped whole, a replay with a
+    /// frame missing would play out differently
+    fn parse(text: &str) -> Self {
+        let mut runs = vec![];
+        let mut current: Option<RunRecord> = None;
+        for line in text.lines() {
+            let keyword = line.split_whitespace().next().unwrap_or("");
+            if keyword == "run" {
+                runs.extend(current.take());
+                current = parse_run(line);
+                continue;
+            }
+            let Some(run) = &mut current else { continue };
+            let ok = match keyword {
+                "game" => parse_result(line).map(|r| run.results.push(r)).is_some(),
+                "replay" => parse_replay(line).map(|r| run.replay = Some(r)).is_some(),
+                _ => match (&mut run.replay, ReplayFrame::parse(line)) {
+                    (Some(replay), Some((frame, repeat))) => {
+                        replay.frames.extend(std::iter::repeat_n(frame, repeat));
+                        true
+                    }
+                    _ => false,
+                },
+            };
+            if !ok {
+                current = None;
+            }
+        }
+        runs.extend(current);
+        runs.truncate(MAX_RUNS);
+        RunHistory { runs }
+    }
+
+    fn encode(&self) -> String {
+        self.runs.iter().map(RunRecord::encode).collect()
+    }
+
+    pub fn save(&self) {
+        let _ = save::write_file(HISTORY_FILE, &self.encode());
+    }
+
+    pub fn add(&mut self, run: RunRecord) {
+        self.runs.insert(0, run);
+        self.runs.truncate(MAX_RUNS);
+    }
+
+    /// Recording of the highest scoring run on `seed`, newest first on a tie
+    pub fn best_replay(&self, mode: GameMode, seed: u64) -> Option<&Replay> {
+        self.runs
+            .iter()
+            .filter(|r| r.mode == mode && r.seed == seed)
+            .filter_map(|r| Some((r.score, r.replay.as_ref()?)))
+            .rev()
+            .max_by_key(|(score, _)| *score)
+            .map(|(_, replay)| replay)
+    }
+
+    pub fn runs(&self) -> &[RunRecord] {
+        &self.runs
+    }
+}
+
+/// Past runs to watch again or retry, opened from the main menu
+pub struct HistoryData {
+    history: RunHistory,
+    selected: Option<usize>,
+    mouse_pos: Vec2,
+}
+
+impl HistoryData {
+    pub fn load() -> Self {
+        let history = RunHistory::load();
+        HistoryData {
+            selected: (!history.runs.is_empty()).then_some(0),
+            history,
+            mouse_pos: Vec2::ZERO,
+        }
+    }
+
+    pub fn run(&self, index: usize) -> Option<&RunRecord> {
+        self.history.runs.get(index)
+    }
+
+    fn selected_run(&self) -> Option<&RunRecord> {
+        self.selected.and_then(|i| self.run(i))
+    }
+}
+
+fn row_button(index: usize) -> Button {
+    Button::new(
+        80.0,
+        ROWS_TOP + ROW_HEIGHT * index as f32,
+        800.0,
+        ROW_HEIGHT - 4.0,
+    )
+}
+
+pub fn update(
+    game_data: &mut GameState,
+    input: FrameInput,
+    delta: f32,
+    events: &mut Vec<GameEvents>,
+) -> Result<(), ()> {
+    if let ActiveState::History(h_data) = &mut game_data.active_state {
+        h_data.mouse_pos = input.mouse_position;
+        if let Some(i) = (0..h_data.history.runs.len()).find(|i| row_button(*i).clicked(&input)) {
+            h_data.selected = Some(i);
+        }
+
+        let selected = h_data.selected.zip(h_data.selected_run());
+        if let Some((index, run)) = selected {
+            if WATCH_BUTTON.clicked(&input) && run.replay.is_some() {
+                events.push(GameEvents::WatchRun(index));
+            } else if RETRY_BUTTON.clicked(&input) {
+                events.push(GameEvents::RetryRun {
+                    mode: run.mode,
+                    seed: run.seed,
+                });
+            }
+        }
+        if BACK_BUTTON.clicked(&input) {
+            events.push(GameEvents::MainMenuReturn);
+        }
+        Ok(())
+    } else {
+        Err(())
+    }
+}
+
+pub fn draw(game_data: &GameState) -> Result<(), ()> {
+    if let ActiveState::History(h_data) = &game_data.active_state {
+        let strings = &game_data.strings;
+        clear_background(Color::new(0.08, 0.08, 0.12, 1.0));
+        draw_text_centered(
+            strings.get("history.title"),
+            Vec2::new(480.0, 56.0),
+            64.0,
+            WHITE,
+        );
+        if h_data.history.runs.is_empty() {
+            draw_text_centered(
+                strings.get("history.empty"),
+                Vec2::new(480.0, 280.0),
+                36.0,
+                GRAY,
+            );
+        }
+
+        for (i, run) in h_data.history.runs.iter().enumerate() {
+            let (min, max) = row_button(i).range;
+            let fill = if h_data.selected == Some(i) {
+                Color::new(0.2, 0.3, 0.2, 0.9)
+            } else if row_button(i).hovered(&h_data.mouse_pos) {
+                Color::new(0.15, 0.15, 0.2, 0.9)
+            } else {
+                Color::new(0.0, 0.0, 0.0, 0.5)
+            };
+            draw_rectangle(min.x, min.y, max.x - min.x, max.y - min.y, fill);
+            let y = min.y + 23.0;
+            let date = Date::from_days((run.finished / 86_400) as i64);
+            draw_text(&date.code(), 96.0, y, 24.0, LIGHTGRAY);
+            draw_text(strings.get(run.mode.name_key()), 240.0, y, 24.0, WHITE);
+            let (outcome, color) = if run.won {
+                (strings.get("score.cleared"), GREEN)
+            } else {
+                (strings.get("history.lost"), RED)
+            };
+            draw_text(outcome, 420.0, y, 24.0, color);
+            draw_text(&format!("{:06}", run.score), 560.0, y, 24.0, YELLOW);
+            draw_text(
+                &format!("{}/{}", run.wins(), run.results.len()),
+                680.0,
+                y,
+                24.0,
+                WHITE,
+            );
+            if run.replay.is_some() {
+                draw_text(strings.get("history.replay"), 770.0, y, 20.0, SKYBLUE);
+            }
+        }
+
+        // How each microgame went, to spot where the selected run fell apart
+        if let Some(run) = h_data.selected_run() {
+            draw_text(
+                &format!("{} {}", strings.get("history.seed"), run.seed),
+                80.0,
+                470.0,
+                24.0,
+                GRAY,
+            );
+            let size = (800.0 / run.results.len().max(1) as f32).min(24.0);
+            for (i, result) in run.results.iter().enumerate() {
+                let color = match (result.won_at, result.boss) {
+                    (Some(_), _) => GREEN,
+                    (None, true) => MAROON,
+                    (None, false) => RED,
+                };
+                draw_rectangle(80.0 + size * i as f32, 486.0, size - 3.0, size - 3.0, color);
+            }
+        }
+
+        if h_data.selected_run().is_some_and(|r| r.replay.is_some()) {
+            WATCH_BUTTON.draw(strings.get("history.watch"), &h_data.mouse_pos);
+        }
+        if h_data.selected.is_some() {
+            RETRY_BUTTON.draw(strings.get("history.retry"), &h_data.mouse_pos);
+        }
+        BACK_BUTTON.draw(strings.get("menu.back"), &h_data.mouse_pos);
+        Ok(())
+    } else {
+        Err(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(seed: u64, replay: Option<Replay>) -> RunRecord {
+        RunRecord {
+            mode: GameMode::Short,
+            seed,
+            won: seed.is_multiple_of(2),
+            score: 1234,
+            finished: 1_760_000_000,
+            results: vec![
+                MicrogameResult {
+                    name: "asteroids",
+                    boss: false,
+                    stage: 0,
+                    time_limit: 5.0,
+                    won_at: Some(1.7),
+                },
+                MicrogameResult {
+                    name: "boss_pipes",
+                    boss: true,
+                    stage: 1,
+                    time_limit: 12.5,
+                    won_at: None,
+                },
+            ],
+            replay,
+        }
+    }
+
+    fn replay() -> Replay {
+        let mut replay = Replay::new(&Accessibility {
+            timer_multiplier: 1.5,
+            ..Default::default()
+        });
+        let still = FrameInput {
+            mouse_position: Vec2::new(480.3, 300.0),
+            ..Default::default()
+        };
+        for _ in 0..3 {
+            replay.record(&still, 1.0 / 60.0);
+        }
+        let click = FrameInput {
+            mouse_state: MousePressState::JustPressed,
+            key_held: true,
+            ..still
+        };
+        replay.record(&click, 0.0171);
+        replay
+    }
+
+    #[test]
+    fn runs_round_trip_with_their_replays() {
+        let mut history = RunHistory::default();
+        history.add(run(1, None));
+        history.add(run(2, Some(replay())));
+        let text = history.encode();
+        // The three still frames share a line
+        assert_eq!(text.lines().count(), 2 * 3 + 3);
+        let read = RunHistory::parse(&text);
+        assert_eq!(read.runs, history.runs);
+        assert_eq!(read.runs[0].replay.as_ref().unwrap().len(), 4);
+        let (input, delta) = read.runs[0].replay.as_ref().unwrap().frame(3).unwrap();
+        assert_eq!(input.mouse_state, MousePressState::JustPressed);
+        assert_eq!(delta, 0.0171);
+    }
+
+    #[test]
+    fn broken_runs_are_dropped_whole() {
+        let mut history = RunHistory::default();
+        history.add(run(1, None));
+        history.add(run(2, Some(replay())));
+        let text = history.encode().replacen("0.0171", "soon", 1);
+        let read = RunHistory::parse(&text);
+        assert_eq!(read.runs.len(), 1);
+        assert_eq!(read.runs[0].seed, 1);
+    }
+
+    #[test]
+    fn best_replay_is_the_top_scoring_one_on_the_seed() {
+        let mut history = RunHistory::default();
+        let scored = |seed, score, replay: Option<Replay>| RunRecord {
+            score,
+            ..run(seed, replay)
+        };
+        let mut best = replay();
+        best.record(&FrameInput::default(), 0.5);
+        history.add(scored(4, 900, Some(replay())));
+        history.add(scored(4, 2000, None));
+        history.add(scored(4, 1500, Some(best.clone())));
+        history.add(scored(5, 9000, Some(replay())));
+        assert_eq!(history.best_replay(GameMode::Short, 4), Some(&best));
+        assert_eq!(history.best_replay(GameMode::Daily, 4), None);
+        assert_eq!(history.best_replay(GameMode::Short, 6), None);
+    }
+
+    #[test]
+    fn keeps_only_the_latest_runs() {
+        let mut history = RunHistory::default();
+        for seed in 0..MAX_RUNS as u64 + 3 {
+            history.add(run(seed, None));
+        }
+        assert_eq!(history.runs().len(), MAX_RUNS);
+        assert_eq!(history.runs()[0].seed, MAX_RUNS as u64 + 2);
+    }
+}
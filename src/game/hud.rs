@@ -0,0 +1,302 @@
+use std::collections::HashMap;
+
+use macroquad::prelude::*;
+
+use super::{
+    i18n::Strings,
+    party::PLAYER_COLORS,
+    tween::{Ease, Lerp, Tween},
+    ui::draw_text_centered,
+};
+
+const HEART_SPACING: f32 = 80.0;
+const HEART_BREAK_TIME: f32 = 0.8;
+const HEART_GAIN_TIME: f32 = 0.5;
+const SPEED_UP_TIME: f32 = 1.4;
+/// Matches the pause gameplay takes between boss phases
+pub const PHASE_TIME: f32 = 1.0;
+const PROMPT_TIME: f32 = 0.9;
+const POINTS_TIME: f32 = 1.0;
+
+/// Everything the HUD shows that it doesn't track itself
+pub struct HudInfo {
+    pub lives: u8,
+    pub stage: u8,
+    pub run_length: u8,
+    pub score: u32,
+    /// What the next win's points get multiplied by
+    pub multiplier: f32,
+    /// Whose turn it is, counting from 1, None in solo runs
+    pub player: Option<u8>,
+    /// Remaining microgame time as a fraction of the full timer, None outside microgames
+    pub timer: Option<f32>,
+}
+
+/// Big message that pops up over the middle of the screen
+#[derive(Clone, Copy)]
+enum Banner {
+    SpeedUp,
+    /// Boss phase, counting from 1
+    Phase(u8),
+    ExtraLife,
+}
+
+impl Banner {
+    fn duration(self) -> f32 {
+        match self {
+            Banner::Phase(_) => PHASE_TIME,
+            Banner::SpeedUp | Banner::ExtraLife => SPEED_UP_TIME,
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            Banner::SpeedUp => Color::new(0.8, 0.1, 0.1, 0.9),
+            Banner::Phase(_) => Color::new(0.5, 0.1, 0.6, 0.9),
+            Banner::ExtraLife => Color::new(0.1, 0.6, 0.2, 0.9),
+        }
+    }
+
+    fn text(self, strings: &Strings) -> String {
+        match self {
+            Banner::SpeedUp => strings.get("hud.speed_up").to_string(),
+            Banner::Phase(n) => format!("{} {n}", strings.get("hud.phase")),
+            Banner::ExtraLife => strings.get("hud.extra_life").to_string(),
+        }
+    }
+}
+
+/// Animated parts of the in-game HUD
+pub struct Hud {
+    breaking_heart: Option<(u8, f32)>,
+    gaining_heart: Option<(u8, f32)>,
+    banner: Option<(Banner, f32)>,
+    /// Points just scored, floating up under the score
+    points: Option<(u32, f32)>,
+    time: f32,
+}
+
+impl Default for Hud {
+    fn default() -> Self {
+        Hud {
+            breaking_heart: None,
+            gaining_heart: None,
+            banner: None,
+            points: None,
+            time: 0.0,
+        }
+    }
+}
+
+impl Hud {
+    pub fn update(&mut self, delta: f32) {
+        self.time += delta;
+        if let Some((_, t)) = &mut self.breaking_heart {
+            *t += delta;
+            if *t > HEART_BREAK_TIME {
+                self.breaking_heart = None;
+            }
+        }
+        if let Some((_, t)) = &mut self.gaining_heart {
+            *t += delta;
+            if *t > HEART_GAIN_TIME {
+                self.gaining_heart = None;
+            }
+        }
+        if let Some((banner, t)) = &mut self.banner {
+            *t += delta;
+            if *t > banner.duration() {
+                self.banner = None;
+            }
+        }
+        if let Some((_, t)) = &mut self.points {
+            *t += delta;
+            if *t > POINTS_TIME {
+                self.points = None;
+            }
+        }
+    }
+
+    /// Starts the break animation for the heart in slot `index`
+    pub fn break_heart(&mut self, index: u8) {
+        self.breaking_heart = Some((index, 0.0));
+    }
+
+    /// Pops in the heart in slot `index` and announces the extra life
+    pub fn gain_heart(&mut self, index: u8) {
+        self.gaining_heart = Some((index, 0.0));
+        self.banner = Some((Banner::ExtraLife, 0.0));
+    }
+
+    pub fn add_points(&mut self, points: u32) {
+        self.points = Some((points, 0.0));
+    }
+
+    pub fn announce_speed_up(&mut self) {
+        self.banner = Some((Banner::SpeedUp, 0.0));
+    }
+
+    /// `phase` counts from 1
+    pub fn announce_phase(&mut self, phase: u8) {
+        self.banner = Some((Banner::Phase(phase), 0.0));
+    }
+
+    pub fn draw(&self, info: &HudInfo, textures: &HashMap<&str, Texture2D>, strings: &Strings) {
+        let heart = textures.get("heart").unwrap();
+        for i in 0..info.lives {
+            let x = 16.0 + HEART_SPACING * i as f32;
+            match self.gaining_heart {
+                Some((index, t)) if index == i => draw_gained_heart(heart, x, t),
+                _ => draw_texture(heart, x, 16.0, WHITE),
+            }
+        }
+        if let Some((index, t)) = self.breaking_heart {
+            draw_broken_heart(heart, 16.0 + HEART_SPACING * index as f32, t);
+        }
+
+        draw_text(
+            &format!("{}/{}", info.stage.min(info.run_length), info.run_length),
+            700.0,
+            50.0,
+            40.0,
+            WHITE,
+        );
+        draw_text(&format!("{:06}", info.score), 800.0, 50.0, 40.0, YELLOW);
+        if info.multiplier > 1.0 {
+            draw_text(
+                &format!("x{:.2}", info.multiplier),
+                860.0,
+                80.0,
+                28.0,
+                ORANGE,
+            );
+        }
+        if let Some((points, t)) = self.points {
+            let rise = Tween::new(0.0, 30.0, POINTS_TIME, Ease::OutQuad).sample(t);
+            let fade = Tween::new(1.0, 0.0, 0.4, Ease::InQuad)
+                .with_delay(POINTS_TIME - 0.4)
+                .sample(t);
+            draw_text(
+                &format!("+{points}"),
+                800.0,
+                80.0 - rise,
+                28.0,
+                Color::new(1.0, 1.0, 0.6, fade),
+            );
+        }
+        if let Some(number) = info.player {
+            let color = PLAYER_COLORS[number as usize - 1];
+            draw_text(&format!("P{number}"), 630.0, 50.0, 40.0, color);
+        }
+
+        if let Some(t) = info.timer {
+            draw_rectangle(30.0, 560.0, 900.0 * t, 10.0, timer_color(t, self.time));
+        }
+
+        if let Some((banner, t)) = self.banner {
+            draw_banner(banner, &banner.text(strings), t);
+        }
+    }
+}
+
+fn draw_banner(banner: Banner, text: &str, t: f32) {
+    let scale = Tween::new(0.0, 1.0, 0.3, Ease::OutBack).sample(t);
+    let alpha = Tween::new(1.0, 0.0, 0.3, Ease::InQuad)
+        .with_delay(banner.duration() - 0.3)
+        .sample(t);
+    let size = Vec2::new(420.0, 110.0) * scale;
+    let center = Vec2::new(480.0, 300.0);
+    draw_rectangle(
+        center.x - size.x / 2.0,
+        center.y - size.y / 2.0,
+        size.x,
+        size.y,
+        Color {
+            a: banner.color().a * alpha,
+            ..banner.color()
+        },
+    );
+    if scale > 0.2 {
+        draw_text_centered(text, center, 72.0 * scale, Color::new(1.0, 1.0, 1.0, alpha));
+    }
+}
+
+/// Instruction text that pops up over the start of a microgame
+pub fn draw_prompt(text: &str, elapsed: f32) {
+    if elapsed > PROMPT_TIME {
+        return;
+    }
+    let scale = Tween::new(0.4, 1.0, 0.25, Ease::OutBack).sample(elapsed);
+    let alpha = Tween::new(1.0, 0.0, 0.25, Ease::InQuad)
+        .with_delay(PROMPT_TIME - 0.25)
+        .sample(elapsed);
+    let size = 96.0 * scale;
+    let center = Vec2::new(480.0, 300.0);
+    for offset in [
+        Vec2::new(-4.0, 0.0),
+        Vec2::new(4.0, 0.0),
+        Vec2::new(0.0, -4.0),
+        Vec2::new(0.0, 4.0),
+    ] {
+        draw_text_centered(
+            text,
+            center + offset,
+            size,
+            Color::new(0.0, 0.0, 0.0, alpha),
+        );
+    }
+    draw_text_centered(text, center, size, Color::new(1.0, 0.9, 0.2, alpha));
+}
+
+/// Yellow while there's plenty of time, sliding to a flashing red near the end
+fn timer_color(fraction: f32, time: f32) -> Color {
+    if fraction > 0.5 {
+        YELLOW
+    } else if fraction > 0.25 {
+        YELLOW.lerp(ORANGE, (0.5 - fraction) * 4.0)
+    } else {
+        let flash = (time * 16.0).sin() * 0.5 + 0.5;
+        ORANGE.lerp(RED, 0.5 + flash * 0.5)
+    }
+}
+
+/// Heart growing into its slot with a bounce
+fn draw_gained_heart(heart: &Texture2D, x: f32, t: f32) {
+    let scale = Tween::new(0.0, 1.0, HEART_GAIN_TIME, Ease::OutBack).sample(t);
+    let size = heart.size() * scale;
+    let center = Vec2::new(x, 16.0) + heart.size() / 2.0;
+    draw_texture_ex(
+        heart,
+        center.x - size.x / 2.0,
+        center.y - size.y / 2.0,
+        WHITE,
+        DrawTextureParams {
+            dest_size: Some(size),
+            ..Default::default()
+        },
+    );
+}
+
+/// Heart split into halves that tumble away and fade
+fn draw_broken_heart(heart: &Texture2D, x: f32, t: f32) {
+    let half = heart.width() / 2.0;
+    let fall = Tween::new(0.0, 140.0, HEART_BREAK_TIME, Ease::InQuad).sample(t);
+    let spread = Tween::new(0.0, 24.0, HEART_BREAK_TIME, Ease::OutQuad).sample(t);
+    let spin = Tween::new(0.0, 0.8, HEART_BREAK_TIME, Ease::OutQuad).sample(t);
+    let color = WHITE.lerp(Color::new(1.0, 0.3, 0.3, 0.0), t / HEART_BREAK_TIME);
+
+    for (side, dir) in [(0.0, -1.0), (1.0, 1.0)] {
+        draw_texture_ex(
+            heart,
+            x + half * side + spread * dir,
+            16.0 + fall,
+            color,
+            DrawTextureParams {
+                source: Some(Rect::new(half * side, 0.0, half, heart.height())),
+                dest_size: Some(Vec2::new(half, heart.height())),
+                rotation: spin * dir,
+                ..Default::default()
+            },
+        );
+    }
+}
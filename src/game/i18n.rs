@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+
+use super::save::parse_kv;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Language {
+    English,
+    Spanish,
+    French,
+}
+
+impl Language {
+    pub const ALL: [Language; 3] = [Language::English, Language::Spanish, Language::French];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::Spanish => "es",
+            Language::French => "fr",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Language> {
+        Language::ALL.into_iter().find(|l| l.code() == code)
+    }
+
+    pub fn next(self) -> Language {
+        let i = Language::ALL.iter().position(|l| *l == self).unwrap();
+        Language::ALL[(i + 1) % Language::ALL.len()]
+    }
+
+    fn table_source(self) -> &'static str {
+        match self {
+            Language::English => include_str!("../../assets/lang/en.txt"),
+            Language::Spanish => include_str!("../../assets/lang/es.txt"),
+            Language::French => include_str!("../../assets/lang/fr.txt"),
+        }
+    }
+}
+
+/// String table for the chosen language, missing keys fall back to English
+pub struct Strings {
+    language: Language,
+    table: HashMap<String, String>,
+    fallback: HashMap<String, String>,
+}
+
+impl Strings {
+    pub fn new(language: Language) -> Self {
+        Strings {
+            language,
+            table: parse_kv(language.table_source()),
+            fallback: parse_kv(Language::English.table_source()),
+        }
+    }
+
+    pub fn language(&self) -> Language {
+        self.language
+    }
+
+    /// Looks up `key`, giving back the key itself if no table has it
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.table
+            .get(key)
+            .or_else(|| self.fallback.get(key))
+            .map(String::as_str)
+            .unwrap_or(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{microgames::gen_microgame, rng::Rng};
+
+    #[test]
+    fn every_microgame_has_an_english_prompt() {
+        let strings = Strings::new(Language::English);
+        for id in 0..=10 {
+            let key = gen_microgame(id, &Rng::new(0)).prompt_key();
+            assert_ne!(strings.get(key), key, "missing {key}");
+        }
+    }
+
+    #[test]
+    fn translations_cover_the_english_table() {
+        let english = parse_kv(Language::English.table_source());
+        for language in Language::ALL {
+            let table = parse_kv(language.table_source());
+            for key in english.keys() {
+                assert!(
+                    table.contains_key(key),
+                    "{} is missing {key}",
+                    language.code()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn missing_keys_fall_back() {
+        let mut strings = Strings::new(Language::Spanish);
+        strings.table.remove("menu.back");
+        assert_eq!(strings.get("menu.back"), "BACK");
+        assert_eq!(strings.get("no.such.key"), "no.such.key");
+    }
+
+    #[test]
+    fn language_codes_round_trip() {
+        for language in Language::ALL {
+            assert_eq!(Language::from_code(language.code()), Some(language));
+        }
+        assert_eq!(Language::from_code("xx"), None);
+    }
+}
@@ -1,6 +1,16 @@
 use macroquad::prelude::*;
 
-use super::{ActiveState, FrameInput, GameEvents, GameState, MousePressState};
+use super::{
+    doors_closing, doors_opening, draw_doors, party::PlayStyle, ui::Button, ActiveState,
+    FrameInput, GameEvents, GameState, MousePressState,
+};
+
+const OPTIONS_BUTTON: Button = Button::new(580.0, 500.0, 160.0, 36.0);
+const MODE_BUTTON: Button = Button::new(750.0, 500.0, 160.0, 36.0);
+const STYLE_BUTTON: Button = Button::new(580.0, 546.0, 160.0, 36.0);
+const ONLINE_BUTTON: Button = Button::new(750.0, 546.0, 160.0, 36.0);
+const ACHIEVEMENTS_BUTTON: Button = Button::new(410.0, 546.0, 160.0, 36.0);
+const HISTORY_BUTTON: Button = Button::new(410.0, 500.0, 160.0, 36.0);
 
 #[derive(Debug, PartialEq)]
 enum TransState {
@@ -33,17 +43,12 @@ pub fn update(
 ) -> Result<(), ()> {
     if let ActiveState::MainMenu(mm_data) = &mut game_data.active_state {
         // Check if player is over play button
-        if 580.0 < input.mouse_position.x
+        mm_data.play_button_hover = 580.0 < input.mouse_position.x
             && input.mouse_position.x < 910.0
             && 333.0 < input.mouse_position.y
-            && input.mouse_position.y < 490.0
-        {
-            mm_data.play_button_hover = true;
-        } else {
-            mm_data.play_button_hover = false;
-        }
+            && input.mouse_position.y < 490.0;
 
-        mm_data.mouse_pos = input.mouse_position.clone();
+        mm_data.mouse_pos = input.mouse_position;
 
         if input.mouse_state == MousePressState::JustPressed
             && mm_data.play_button_hover
@@ -52,6 +57,32 @@ pub fn update(
             mm_data.t_state = TransState::Out(0.5);
         }
 
+        if OPTIONS_BUTTON.clicked(&input) && mm_data.t_state == TransState::None {
+            events.push(GameEvents::OpenOptions);
+        }
+
+        if MODE_BUTTON.clicked(&input) && mm_data.t_state == TransState::None {
+            game_data.settings.mode = game_data.settings.mode.next();
+            game_data.settings.save();
+        }
+
+        if STYLE_BUTTON.clicked(&input) && mm_data.t_state == TransState::None {
+            game_data.settings.style = game_data.settings.style.next();
+            game_data.settings.save();
+        }
+
+        if ONLINE_BUTTON.clicked(&input) && mm_data.t_state == TransState::None {
+            events.push(GameEvents::OpenLobby);
+        }
+
+        if ACHIEVEMENTS_BUTTON.clicked(&input) && mm_data.t_state == TransState::None {
+            events.push(GameEvents::OpenGallery);
+        }
+
+        if HISTORY_BUTTON.clicked(&input) && mm_data.t_state == TransState::None {
+            events.push(GameEvents::OpenHistory);
+        }
+
         mm_data.t_state = match &mm_data.t_state {
             TransState::Out(t) => {
                 if *t <= 0.0 {
@@ -69,15 +100,11 @@ pub fn update(
             TransState::None => TransState::None,
         };
 
-        return Ok(());
+        Ok(())
     } else {
-        return Err(());
+        Err(())
     }
 }
-fn lerp(f: f32, t: f32, d: f32) -> f32 {
-    t * d + f * (1.0 - d)
-}
-
 pub fn draw(game_data: &GameState) -> Result<(), ()> {
     if let ActiveState::MainMenu(mm_data) = &game_data.active_state {
         draw_texture(
@@ -104,44 +131,37 @@ pub fn draw(game_data: &GameState) -> Result<(), ()> {
             );
         }
 
-        let r_door = game_data.textures.get("right_door").unwrap();
-        let l_door = game_data.textures.get("left_door").unwrap();
+        OPTIONS_BUTTON.draw(game_data.strings.get("menu.options"), &mm_data.mouse_pos);
+        MODE_BUTTON.draw(
+            game_data.strings.get(game_data.settings.mode.name_key()),
+            &mm_data.mouse_pos,
+        );
+        let style = game_data.settings.style;
+        let style_name = game_data.strings.get(style.name_key());
+        let style_label = match style {
+            PlayStyle::Solo | PlayStyle::Duel => style_name.to_string(),
+            _ => format!("{style_name} x{}", style.players()),
+        };
+        STYLE_BUTTON.draw(&style_label, &mm_data.mouse_pos);
+        ONLINE_BUTTON.draw(game_data.strings.get("menu.online"), &mm_data.mouse_pos);
+        ACHIEVEMENTS_BUTTON.draw(
+            game_data.strings.get("menu.achievements"),
+            &mm_data.mouse_pos,
+        );
+        HISTORY_BUTTON.draw(game_data.strings.get("menu.history"), &mm_data.mouse_pos);
+
         match mm_data.t_state {
             TransState::Out(t) => {
-                let raw_progress = clamp((0.5 - t) * 2.0, 0.0, 1.0);
-                draw_texture(
-                    l_door,
-                    lerp(-500.0, 0.0, raw_progress * raw_progress),
-                    0.0,
-                    WHITE,
-                );
-                draw_texture(
-                    r_door,
-                    lerp(1000.0, 462.0, raw_progress * raw_progress),
-                    0.0,
-                    WHITE,
-                );
+                draw_doors(&game_data.textures, doors_closing(0.5).sample(0.5 - t))
             }
             TransState::In(t) => {
-                let raw_progress = clamp((0.5 - t) * 2.0, 0.0, 1.0);
-                draw_texture(
-                    l_door,
-                    lerp(0.0, -500.0, raw_progress * raw_progress),
-                    0.0,
-                    WHITE,
-                );
-                draw_texture(
-                    r_door,
-                    lerp(462.0, 1000.0, raw_progress * raw_progress),
-                    0.0,
-                    WHITE,
-                );
+                draw_doors(&game_data.textures, doors_opening(0.5).sample(0.5 - t))
             }
             _ => {}
         }
 
-        return Ok(());
+        Ok(())
     } else {
-        return Err(());
+        Err(())
     }
 }
@@ -1,13 +1,35 @@
 use macroquad::prelude::*;
 
-use crate::game::FrameInput;
+use crate::game::{microgames::MicrogameStatus, FrameInput, GameEvents};
+
+pub const PROMPT: &str = "prompt.always_win";
 
 pub struct Data {}
 
-pub fn update(data: &mut Data, input: FrameInput, delta: f32) -> bool {
-    true
+pub fn update(
+    data: &mut Data,
+    input: FrameInput,
+    delta: f32,
+    events: &mut Vec<GameEvents>,
+) -> MicrogameStatus {
+    MicrogameStatus::Won
 }
 
 pub fn draw(data: &Data) {
     clear_background(WHITE);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::microgames::test_input::{hover, FRAME};
+
+    #[test]
+    fn wins_without_doing_anything() {
+        let mut data = Data {};
+        assert_eq!(
+            update(&mut data, hover(0.0, 0.0), FRAME, &mut vec![]),
+            MicrogameStatus::Won
+        );
+    }
+}
@@ -1,9 +1,22 @@
 use std::collections::HashMap;
 
-use crate::game::{particles_draw, particles_update, FrameInput, MousePressState, Particle};
+#[cfg(feature = "dev")]
+use crate::game::dev::Hitbox;
+use crate::game::{
+    bot::BotAction,
+    microgames::MicrogameStatus,
+    particles::{self, Emitter},
+    rng::Rng,
+    settings::Accessibility,
+    FrameInput, GameEvents, MousePressState,
+};
 use macroquad::prelude::*;
 use rand::gen_range;
 
+pub const PROMPT: &str = "prompt.asteroids";
+/// How far off the asteroid's center the lazer can be and still hit
+const HIT_BAND: f32 = 150.0;
+
 pub struct Data {
     asteroid_pos: Vec2,
     asteroid_vel: Vec2,
@@ -11,30 +24,38 @@ pub struct Data {
     lazer_vel: f32,
     did_shoot: bool,
     did_hit: bool,
+    /// Frames in a row the lazer has been lined up with the asteroid
+    aligned_frames: u32,
     lazer_cooltime: f32,
     chunks_pos: Vec<Vec2>,
     chunks_vel: Vec<Vec2>,
-    particles: Vec<Particle>,
+    explosion: Emitter,
 }
 
-impl Default for Data {
-    fn default() -> Self {
+impl Data {
+    pub fn new(rng: &Rng) -> Self {
         Data {
-            asteroid_pos: Vec2::new(gen_range(300.0, 850.0), gen_range(150.0, 490.0)),
-            asteroid_vel: Vec2::new(gen_range(-10.0, 10.0), gen_range(-10.0, 10.0)),
+            asteroid_pos: Vec2::new(rng.gen_range(300.0, 850.0), rng.gen_range(150.0, 490.0)),
+            asteroid_vel: Vec2::new(rng.gen_range(-10.0, 10.0), rng.gen_range(-10.0, 10.0)),
             lazer_pos: 200.0,
             lazer_vel: 500.0,
             did_shoot: false,
             did_hit: false,
+            aligned_frames: 0,
             lazer_cooltime: 0.0,
             chunks_pos: vec![],
             chunks_vel: vec![],
-            particles: vec![],
+            explosion: Emitter::new(Vec2::ZERO, particles::asteroid_explosion()),
         }
     }
 }
 
-pub fn update(data: &mut Data, input: FrameInput, delta: f32) -> bool {
+pub fn update(
+    data: &mut Data,
+    input: FrameInput,
+    delta: f32,
+    events: &mut Vec<GameEvents>,
+) -> MicrogameStatus {
     data.lazer_pos += data.lazer_vel * delta;
     if data.lazer_pos > 540.0 {
         data.lazer_pos = 540.0;
@@ -48,12 +69,19 @@ pub fn update(data: &mut Data, input: FrameInput, delta: f32) -> bool {
     data.asteroid_pos += data.asteroid_vel * delta;
     data.lazer_cooltime -= delta;
 
+    let aligned = (data.lazer_pos - data.asteroid_pos.y).abs() < HIT_BAND;
+    data.aligned_frames = if aligned { data.aligned_frames + 1 } else { 0 };
+
     if input.mouse_state == MousePressState::JustPressed && !data.did_shoot {
         data.did_shoot = true;
         data.lazer_vel = 0.0;
         data.lazer_cooltime = 0.25;
+        events.push(GameEvents::LaserFired {
+            hit: aligned,
+            first_frame: data.aligned_frames == 1,
+        });
 
-        if (data.lazer_pos - data.asteroid_pos.y).abs() < 150.0 {
+        if aligned {
             data.did_hit = true;
             for _ in 0..6 {
                 data.chunks_pos.push(
@@ -62,41 +90,48 @@ pub fn update(data: &mut Data, input: FrameInput, delta: f32) -> bool {
                 data.chunks_vel
                     .push(Vec2::new(gen_range(-50.0, 50.0), gen_range(-50.0, 50.0)));
             }
-            for _ in 0..32 {
-                data.particles.push(Particle {
-                    position: data.asteroid_pos
-                        + Vec2::new(gen_range(-50.0, 50.0), gen_range(-50.0, 50.0)),
-                    velocity: Vec2::new(gen_range(-200.0, 200.0), gen_range(-200.0, 200.0)),
-                    rotation: gen_range(0.0, 6.2),
-                    ang_velocity: gen_range(-1.0, 1.0),
-                    color: Color {
-                        r: 0.8,
-                        g: 0.4,
-                        b: 0.4,
-                        a: 0.5,
-                    },
-                    color_delta: Color {
-                        r: 0.0,
-                        g: 0.0,
-                        b: 0.0,
-                        a: -0.1,
-                    },
-                    lifetime: 0.5,
-                });
-            }
+            data.explosion.position = data.asteroid_pos;
+            data.explosion.burst();
+            events.push(GameEvents::HitStop(0.08));
+            events.push(GameEvents::ScreenShake(0.6));
+            events.push(GameEvents::ZoomPunch(0.08));
+        } else {
+            events.push(GameEvents::ScreenShake(0.2));
         }
     }
 
-    particles_update(&mut data.particles, &delta);
+    data.explosion.update(delta);
 
     for (pos, vel) in data.chunks_pos.iter_mut().zip(data.chunks_vel.iter_mut()) {
         *pos += *vel * delta;
     }
 
-    data.did_hit
+    match (data.did_shoot, data.did_hit) {
+        (false, _) => MicrogameStatus::Running,
+        (true, true) => MicrogameStatus::WonEarly,
+        (true, false) => MicrogameStatus::Lost,
+    }
+}
+
+pub fn bot_action(data: &Data) -> BotAction {
+    if !data.did_shoot && (data.lazer_pos - data.asteroid_pos.y).abs() < 100.0 {
+        BotAction::Click(Vec2::new(480.0, 300.0))
+    } else {
+        BotAction::Idle
+    }
+}
+
+/// The band the lazer has to be inside when fired to hit
+#[cfg(feature = "dev")]
+pub fn hitboxes(data: &Data) -> Vec<Hitbox> {
+    let y = data.asteroid_pos.y;
+    vec![Hitbox::Rect((
+        Vec2::new(0.0, y - HIT_BAND),
+        Vec2::new(960.0, y + HIT_BAND),
+    ))]
 }
 
-pub fn draw(data: &Data, textures: &HashMap<&str, Texture2D>) {
+pub fn draw(data: &Data, textures: &HashMap<&str, Texture2D>, access: &Accessibility) {
     draw_texture(textures.get("asteroids_bkgd").unwrap(), 0.0, 0.0, WHITE);
     if !data.did_shoot {
         draw_rectangle(0.0, data.lazer_pos - 5.0, 1000.0, 10.0, PINK);
@@ -123,6 +158,22 @@ pub fn draw(data: &Data, textures: &HashMap<&str, Texture2D>) {
         WHITE,
     );
 
+    // Arrow while the laser is ready, cross once it has fired
+    if access.colorblind {
+        let (x, y) = (40.0, data.lazer_pos);
+        if data.did_shoot {
+            draw_line(x - 14.0, y - 14.0, x + 14.0, y + 14.0, 6.0, WHITE);
+            draw_line(x - 14.0, y + 14.0, x + 14.0, y - 14.0, 6.0, WHITE);
+        } else {
+            draw_triangle(
+                Vec2::new(x - 12.0, y - 16.0),
+                Vec2::new(x - 12.0, y + 16.0),
+                Vec2::new(x + 16.0, y),
+                WHITE,
+            );
+        }
+    }
+
     if data.lazer_cooltime > 0.0 {
         draw_texture(
             textures.get("asteroids_beam").unwrap(),
@@ -145,5 +196,81 @@ pub fn draw(data: &Data, textures: &HashMap<&str, Texture2D>) {
         );
     }
 
-    particles_draw(&data.particles, textures.get("smoke_particle").unwrap());
+    data.explosion.draw(textures);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::microgames::test_input::{click, FRAME};
+
+    /// Still asteroid with the lazer parked at y 300
+    fn parked(asteroid_y: f32) -> Data {
+        Data {
+            asteroid_pos: Vec2::new(600.0, asteroid_y),
+            asteroid_vel: Vec2::ZERO,
+            lazer_pos: 300.0,
+            lazer_vel: 0.0,
+            ..Data::new(&Rng::new(0))
+        }
+    }
+
+    #[test]
+    fn lined_up_shot_hits() {
+        let mut data = parked(300.0);
+        let mut events = vec![];
+        assert_eq!(
+            update(&mut data, click(480.0, 300.0), FRAME, &mut events),
+            MicrogameStatus::WonEarly
+        );
+        assert!(events.contains(&GameEvents::HitStop(0.08)));
+    }
+
+    #[test]
+    fn reports_shots_on_the_first_lined_up_frame() {
+        let mut data = parked(300.0);
+        let mut events = vec![];
+        update(&mut data, click(480.0, 300.0), FRAME, &mut events);
+        assert!(events.contains(&GameEvents::LaserFired {
+            hit: true,
+            first_frame: true
+        }));
+
+        let mut data = parked(300.0);
+        let mut events = vec![];
+        update(&mut data, FrameInput::default(), FRAME, &mut events);
+        update(&mut data, click(480.0, 300.0), FRAME, &mut events);
+        assert!(events.contains(&GameEvents::LaserFired {
+            hit: true,
+            first_frame: false
+        }));
+    }
+
+    #[test]
+    fn only_one_shot_allowed() {
+        let mut data = parked(500.0);
+        assert_eq!(
+            update(&mut data, FrameInput::default(), FRAME, &mut vec![]),
+            MicrogameStatus::Running
+        );
+        assert_eq!(
+            update(&mut data, click(480.0, 300.0), FRAME, &mut vec![]),
+            MicrogameStatus::Lost
+        );
+        data.asteroid_pos.y = 300.0;
+        assert_eq!(
+            update(&mut data, click(480.0, 300.0), FRAME, &mut vec![]),
+            MicrogameStatus::Lost
+        );
+    }
+
+    #[test]
+    fn lazer_bounces_inside_its_track() {
+        let mut data = parked(300.0);
+        data.lazer_pos = 530.0;
+        data.lazer_vel = 500.0;
+        update(&mut data, FrameInput::default(), 0.1, &mut vec![]);
+        assert_eq!(data.lazer_pos, 540.0);
+        assert!(data.lazer_vel < 0.0);
+    }
 }
@@ -0,0 +1,283 @@
+use std::collections::HashMap;
+
+#[cfg(feature = "dev")]
+use crate::game::dev::Hitbox;
+use crate::game::{
+    bot::BotAction,
+    microgames::MicrogameStatus,
+    particles::{self, Emitter},
+    rng::Rng,
+    settings::Accessibility,
+    FrameInput, GameEvents, MousePressState,
+};
+use macroquad::prelude::*;
+use rand::gen_range;
+
+pub const PROMPT: &str = "prompt.boss_asteroid";
+/// Fixed timer, bosses don't follow the run's timer tiers
+pub const TIME: f32 = 12.0;
+
+const HITS: u8 = 3;
+const SIZE: f32 = 420.0;
+/// How far off the middle of the asteroid the lazer can be and still hit
+const HIT_BAND: f32 = 130.0;
+const LAZER_SPEED: f32 = 450.0;
+/// Extra lazer speed for every hit landed
+const LAZER_SPEED_UP: f32 = 250.0;
+const DRIFT_SPEED: f32 = 60.0;
+const RELOAD_TIME: f32 = 0.5;
+const MISS_RELOAD_TIME: f32 = 1.0;
+
+pub struct Data {
+    asteroid_pos: Vec2,
+    asteroid_vel: Vec2,
+    hp: u8,
+    lazer_pos: f32,
+    lazer_dir: f32,
+    reload: f32,
+    lazer_cooltime: f32,
+    chunks_pos: Vec<Vec2>,
+    chunks_vel: Vec<Vec2>,
+    explosion: Emitter,
+}
+
+impl Data {
+    pub fn new(rng: &Rng) -> Self {
+        let angle = rng.gen_range(0.0, std::f32::consts::TAU);
+        Data {
+            asteroid_pos: Vec2::new(rng.gen_range(500.0, 750.0), rng.gen_range(220.0, 420.0)),
+            asteroid_vel: Vec2::from_angle(angle) * DRIFT_SPEED,
+            hp: HITS,
+            lazer_pos: 200.0,
+            lazer_dir: 1.0,
+            reload: 0.0,
+            lazer_cooltime: 0.0,
+            chunks_pos: vec![],
+            chunks_vel: vec![],
+            explosion: Emitter::new(Vec2::ZERO, particles::asteroid_explosion()),
+        }
+    }
+}
+
+/// Hits landed so far, each one starts a new phase
+pub fn phase(data: &Data) -> u8 {
+    HITS - data.hp
+}
+
+fn lazer_speed(data: &Data) -> f32 {
+    LAZER_SPEED + LAZER_SPEED_UP * phase(data) as f32
+}
+
+pub fn update(
+    data: &mut Data,
+    input: FrameInput,
+    delta: f32,
+    events: &mut Vec<GameEvents>,
+) -> MicrogameStatus {
+    data.lazer_pos += lazer_speed(data) * data.lazer_dir * delta;
+    if data.lazer_pos > 540.0 {
+        data.lazer_pos = 540.0;
+        data.lazer_dir = -1.0;
+    }
+    if data.lazer_pos < 100.0 {
+        data.lazer_pos = 100.0;
+        data.lazer_dir = 1.0;
+    }
+
+    // Drifts around the right side, bouncing off the edges
+    if data.hp > 0 {
+        data.asteroid_pos += data.asteroid_vel * (1.0 + phase(data) as f32 * 0.5) * delta;
+        if !(450.0..=800.0).contains(&data.asteroid_pos.x) {
+            data.asteroid_vel.x *= -1.0;
+            data.asteroid_pos.x = data.asteroid_pos.x.clamp(450.0, 800.0);
+        }
+        if !(180.0..=460.0).contains(&data.asteroid_pos.y) {
+            data.asteroid_vel.y *= -1.0;
+            data.asteroid_pos.y = data.asteroid_pos.y.clamp(180.0, 460.0);
+        }
+    }
+
+    data.reload -= delta;
+    data.lazer_cooltime -= delta;
+
+    if input.mouse_state == MousePressState::JustPressed && data.reload <= 0.0 && data.hp > 0 {
+        data.lazer_cooltime = 0.25;
+        if (data.lazer_pos - data.asteroid_pos.y).abs() < HIT_BAND {
+            data.hp -= 1;
+            data.reload = RELOAD_TIME;
+            for _ in 0..4 {
+                data.chunks_pos.push(
+                    data.asteroid_pos + Vec2::new(gen_range(-80.0, 80.0), gen_range(-80.0, 80.0)),
+                );
+                data.chunks_vel
+                    .push(Vec2::new(gen_range(-80.0, 80.0), gen_range(-80.0, 80.0)));
+            }
+            data.explosion.position = data.asteroid_pos;
+            data.explosion.burst();
+            events.push(GameEvents::HitStop(0.08));
+            let shake = if data.hp == 0 { 0.9 } else { 0.5 };
+            events.push(GameEvents::ScreenShake(shake));
+            events.push(GameEvents::ZoomPunch(0.08));
+        } else {
+            data.reload = MISS_RELOAD_TIME;
+            events.push(GameEvents::ScreenShake(0.2));
+        }
+    }
+
+    data.explosion.update(delta);
+
+    for (pos, vel) in data.chunks_pos.iter_mut().zip(data.chunks_vel.iter_mut()) {
+        *pos += *vel * delta;
+    }
+
+    MicrogameStatus::done_when(data.hp == 0)
+}
+
+pub fn bot_action(data: &Data) -> BotAction {
+    if data.hp > 0 && data.reload <= 0.0 && (data.lazer_pos - data.asteroid_pos.y).abs() < 60.0 {
+        BotAction::Click(Vec2::new(480.0, 300.0))
+    } else {
+        BotAction::Idle
+    }
+}
+
+/// The band the lazer has to be inside when fired to hit
+#[cfg(feature = "dev")]
+pub fn hitboxes(data: &Data) -> Vec<Hitbox> {
+    let y = data.asteroid_pos.y;
+    vec![Hitbox::Rect((
+        Vec2::new(0.0, y - HIT_BAND),
+        Vec2::new(960.0, y + HIT_BAND),
+    ))]
+}
+
+pub fn draw(data: &Data, textures: &HashMap<&str, Texture2D>, access: &Accessibility) {
+    draw_texture(textures.get("asteroids_bkgd").unwrap(), 0.0, 0.0, WHITE);
+    let ready = data.reload <= 0.0;
+    if ready {
+        draw_rectangle(0.0, data.lazer_pos - 5.0, 1000.0, 10.0, PINK);
+    }
+    if data.hp > 0 {
+        // Reddens with every hit taken
+        let hurt = phase(data) as f32 / HITS as f32;
+        draw_texture_ex(
+            textures.get("asteroids_asteroid").unwrap(),
+            data.asteroid_pos.x - SIZE / 2.0,
+            data.asteroid_pos.y - SIZE / 2.0,
+            Color::new(1.0, 1.0 - hurt * 0.6, 1.0 - hurt * 0.6, 1.0),
+            DrawTextureParams {
+                dest_size: Some(Vec2::splat(SIZE)),
+                ..Default::default()
+            },
+        );
+    }
+
+    draw_texture(
+        textures
+            .get(if ready {
+                "asteroids_lazer_green"
+            } else {
+                "asteroids_lazer_red"
+            })
+            .unwrap(),
+        0.0,
+        data.lazer_pos - 70.0,
+        WHITE,
+    );
+
+    // Arrow while the laser is ready, cross while it reloads
+    if access.colorblind {
+        let (x, y) = (40.0, data.lazer_pos);
+        if !ready {
+            draw_line(x - 14.0, y - 14.0, x + 14.0, y + 14.0, 6.0, WHITE);
+            draw_line(x - 14.0, y + 14.0, x + 14.0, y - 14.0, 6.0, WHITE);
+        } else {
+            draw_triangle(
+                Vec2::new(x - 12.0, y - 16.0),
+                Vec2::new(x - 12.0, y + 16.0),
+                Vec2::new(x + 16.0, y),
+                WHITE,
+            );
+        }
+    }
+
+    if data.lazer_cooltime > 0.0 {
+        draw_texture(
+            textures.get("asteroids_beam").unwrap(),
+            105.0,
+            data.lazer_pos - 30.0,
+            WHITE,
+        );
+    }
+
+    for (pos, i) in data.chunks_pos.iter().zip(0..) {
+        draw_texture_ex(
+            textures.get("asteroids_chunk").unwrap(),
+            pos.x - 50.0,
+            pos.y - 50.0,
+            WHITE,
+            DrawTextureParams {
+                rotation: i as f32,
+                ..Default::default()
+            },
+        );
+    }
+
+    // Health pips along the top
+    for i in 0..HITS {
+        let color = if i < data.hp { RED } else { DARKGRAY };
+        draw_rectangle(390.0 + 64.0 * i as f32, 70.0, 52.0, 14.0, color);
+    }
+
+    data.explosion.draw(textures);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::microgames::test_input::{click, FRAME};
+
+    fn lined_up() -> Data {
+        let mut data = Data::new(&Rng::new(0));
+        data.asteroid_vel = Vec2::ZERO;
+        data.asteroid_pos.y = 300.0;
+        data.lazer_pos = 300.0;
+        data
+    }
+
+    #[test]
+    fn takes_every_hit_to_win() {
+        let mut data = lined_up();
+        let mut events = vec![];
+        for hit in 1..=HITS {
+            data.lazer_pos = 300.0;
+            let won = update(&mut data, click(480.0, 300.0), FRAME, &mut events);
+            assert_eq!(phase(&data), hit);
+            assert_eq!(won.is_decided(), hit == HITS);
+            data.reload = 0.0;
+        }
+    }
+
+    #[test]
+    fn lazer_speeds_up_with_each_hit() {
+        let mut data = lined_up();
+        let before = lazer_speed(&data);
+        update(&mut data, click(480.0, 300.0), FRAME, &mut vec![]);
+        assert!(lazer_speed(&data) > before);
+    }
+
+    #[test]
+    fn misses_cost_a_longer_reload() {
+        let mut data = lined_up();
+        data.lazer_pos = 100.0;
+        data.asteroid_pos.y = 460.0;
+        update(&mut data, click(480.0, 300.0), FRAME, &mut vec![]);
+        assert_eq!(data.hp, HITS);
+
+        // Lined up again, but still reloading
+        data.lazer_pos = 460.0;
+        data.reload = MISS_RELOAD_TIME - RELOAD_TIME;
+        update(&mut data, click(480.0, 300.0), FRAME, &mut vec![]);
+        assert_eq!(data.hp, HITS);
+    }
+}
@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+
+use macroquad::prelude::*;
+
+#[cfg(feature = "dev")]
+use crate::game::dev::Hitbox;
+
+use crate::game::{
+    bot::BotAction,
+    microgames::MicrogameStatus,
+    particles::{self, Emitter},
+    rng::Rng,
+    FrameInput, GameEvents, MousePressState,
+};
+
+pub const PROMPT: &str = "prompt.boss_pipes";
+/// Fixed timer, bosses don't follow the run's timer tiers
+pub const TIME: f32 = 10.0;
+
+/// Leaks opening in each wave
+const WAVE_SIZES: [usize; 3] = [2, 3, 4];
+
+/// Places a leak can spring, a wave never uses the same one twice
+const SPOTS: [(Vec2, Vec2); 6] = [
+    (Vec2::new(80.0, 100.0), Vec2::new(260.0, 260.0)),
+    (Vec2::new(300.0, 110.0), Vec2::new(500.0, 270.0)),
+    (Vec2::new(560.0, 100.0), Vec2::new(760.0, 260.0)),
+    (Vec2::new(60.0, 360.0), Vec2::new(260.0, 530.0)),
+    (Vec2::new(380.0, 330.0), Vec2::new(580.0, 500.0)),
+    (Vec2::new(680.0, 320.0), Vec2::new(900.0, 500.0)),
+];
+
+struct Leak {
+    range: (Vec2, Vec2),
+    wave: u8,
+    clicked: bool,
+    spray: Emitter,
+}
+
+impl Leak {
+    fn new((min, max): (Vec2, Vec2), wave: u8) -> Self {
+        let mut config = particles::pipes_leak();
+        config.spawn_area = (max - min) / 2.0 - Vec2::splat(50.0);
+        let mut spray = Emitter::new((min + max) / 2.0, config);
+        spray.active = false;
+        Leak {
+            range: (min, max),
+            wave,
+            clicked: false,
+            spray,
+        }
+    }
+
+    fn contains(&self, pos: Vec2) -> bool {
+        pos.x > self.range.0.x
+            && pos.x < self.range.1.x
+            && pos.y > self.range.0.y
+            && pos.y < self.range.1.y
+    }
+}
+
+pub struct Data {
+    leaks: Vec<Leak>,
+    wave: u8,
+}
+
+impl Data {
+    pub fn new(rng: &Rng) -> Self {
+        let mut leaks = vec![];
+        for (wave, size) in WAVE_SIZES.iter().enumerate() {
+            let mut spots = SPOTS;
+            rng.shuffle(&mut spots);
+            leaks.extend(spots[..*size].iter().map(|s| Leak::new(*s, wave as u8)));
+        }
+        Data { leaks, wave: 0 }
+    }
+}
+
+/// Waves patched so far, each one starts a new phase
+pub fn phase(data: &Data) -> u8 {
+    data.wave
+}
+
+fn open_leaks(data: &Data) -> impl Iterator<Item = &Leak> {
+    data.leaks
+        .iter()
+        .filter(move |l| l.wave == data.wave && !l.clicked)
+}
+
+pub fn update(
+    data: &mut Data,
+    input: FrameInput,
+    delta: f32,
+    events: &mut Vec<GameEvents>,
+) -> MicrogameStatus {
+    let wave = data.wave;
+    for l in data.leaks.iter_mut().filter(|l| l.wave == wave) {
+        if input.mouse_state == MousePressState::JustPressed && l.contains(input.mouse_position) {
+            l.clicked = true;
+        }
+    }
+
+    if (data.wave as usize) < WAVE_SIZES.len() && open_leaks(data).next().is_none() {
+        data.wave += 1;
+        if (data.wave as usize) < WAVE_SIZES.len() {
+            // The next lot bursts open
+            events.push(GameEvents::ScreenShake(0.4));
+        }
+    }
+
+    let wave = data.wave;
+    for l in data.leaks.iter_mut() {
+        l.spray.active = l.wave == wave && !l.clicked;
+        l.spray.update(delta);
+    }
+
+    MicrogameStatus::done_when(data.wave as usize == WAVE_SIZES.len())
+}
+
+pub fn bot_action(data: &Data) -> BotAction {
+    match open_leaks(data).next() {
+        Some(l) => BotAction::Click((l.range.0 + l.range.1) / 2.0),
+        None => BotAction::Idle,
+    }
+}
+
+#[cfg(feature = "dev")]
+pub fn hitboxes(data: &Data) -> Vec<Hitbox> {
+    open_leaks(data).map(|l| Hitbox::Rect(l.range)).collect()
+}
+
+pub fn draw(data: &Data, tex_map: &HashMap<&str, Texture2D>) {
+    clear_background(WHITE);
+
+    draw_texture(tex_map.get("pipes_bkgd").unwrap(), 0.0, 0.0, WHITE);
+    for l in data.leaks.iter().filter(|l| l.wave <= data.wave) {
+        l.spray.draw(tex_map);
+        if l.clicked {
+            let center = (l.range.0 + l.range.1) / 2.0;
+            draw_rectangle(center.x - 50.0, center.y - 30.0, 100.0, 60.0, GRAY);
+            draw_rectangle_lines(center.x - 50.0, center.y - 30.0, 100.0, 60.0, 6.0, DARKGRAY);
+        }
+    }
+
+    // Wave counter along the top
+    for i in 0..WAVE_SIZES.len() as u8 {
+        let color = if i < data.wave { SKYBLUE } else { DARKGRAY };
+        draw_rectangle(390.0 + 64.0 * i as f32, 70.0, 52.0, 14.0, color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::microgames::test_input::{click, FRAME};
+
+    fn patch_next(data: &mut Data) -> MicrogameStatus {
+        let c = match bot_action(data) {
+            BotAction::Click(c) => c,
+            _ => panic!("nothing left to patch"),
+        };
+        update(data, click(c.x, c.y), FRAME, &mut vec![])
+    }
+
+    #[test]
+    fn waves_come_in_order() {
+        let mut data = Data::new(&Rng::new(3));
+        for (wave, size) in WAVE_SIZES.iter().enumerate() {
+            assert_eq!(phase(&data), wave as u8);
+            assert_eq!(open_leaks(&data).count(), *size);
+            for _ in 0..*size {
+                patch_next(&mut data);
+            }
+        }
+        assert_eq!(phase(&data) as usize, WAVE_SIZES.len());
+    }
+
+    #[test]
+    fn wins_once_the_last_wave_is_patched() {
+        let mut data = Data::new(&Rng::new(7));
+        let total: usize = WAVE_SIZES.iter().sum();
+        for i in 1..=total {
+            let status = patch_next(&mut data);
+            assert_eq!(status == MicrogameStatus::WonEarly, i == total);
+        }
+    }
+
+    #[test]
+    fn later_waves_stay_shut_until_their_turn() {
+        let mut data = Data::new(&Rng::new(1));
+        let first = data.leaks.iter().find(|l| l.wave == 1).unwrap().range;
+        let c = (first.0 + first.1) / 2.0;
+        update(&mut data, click(c.x, c.y), FRAME, &mut vec![]);
+        assert!(data.leaks.iter().filter(|l| l.wave > 0).all(|l| !l.clicked));
+        assert!(data
+            .leaks
+            .iter()
+            .filter(|l| l.wave > 0)
+            .all(|l| !l.spray.active));
+    }
+}
@@ -1,8 +1,14 @@
-use std::{collections::HashMap, iter::zip, usize};
+use std::{collections::HashMap, iter::zip};
 
-use crate::game::{FrameInput, MousePressState};
+#[cfg(feature = "dev")]
+use crate::game::dev::Hitbox;
+use crate::game::{
+    bot::BotAction, microgames::MicrogameStatus, rng::Rng, settings::Accessibility, FrameInput,
+    GameEvents, MousePressState,
+};
 use macroquad::prelude::*;
-use rand::ChooseRandom;
+
+pub const PROMPT: &str = "prompt.combo";
 
 pub struct Button {
     range: (Vec2, Vec2),
@@ -15,10 +21,10 @@ pub struct Data {
     button_on: u8,
 }
 
-impl Default for Data {
-    fn default() -> Self {
+impl Data {
+    pub fn new(rng: &Rng) -> Self {
         let mut b_order: Vec<u8> = (0..4).collect();
-        b_order.shuffle();
+        rng.shuffle(&mut b_order);
 
         let buttons: Vec<Button> = zip((0..4), b_order)
             .map(|(bnum, order)| Button {
@@ -44,7 +50,12 @@ impl Default for Data {
     }
 }
 
-pub fn update(data: &mut Data, input: FrameInput, delta: f32) -> bool {
+pub fn update(
+    data: &mut Data,
+    input: FrameInput,
+    delta: f32,
+    events: &mut Vec<GameEvents>,
+) -> MicrogameStatus {
     let button_pressed: i8 = if input.mouse_state != MousePressState::JustPressed {
         -1
     } else {
@@ -60,29 +71,65 @@ pub fn update(data: &mut Data, input: FrameInput, delta: f32) -> bool {
                 break;
             }
         }
-        b_pressed as i8
+        b_pressed
     };
 
     if button_pressed != -1 && !data.buttons[button_pressed as usize].pressed {
         if data.button_on == data.buttons[button_pressed as usize].order {
             data.button_on += 1;
             data.buttons[button_pressed as usize].pressed = true;
+            events.push(GameEvents::ButtonPressed);
         } else {
             for button in data.buttons.iter_mut() {
                 button.pressed = false;
             }
             data.button_on = 0;
+            events.push(GameEvents::WrongInput);
         }
     }
 
-    data.buttons
+    MicrogameStatus::done_when(data.buttons.iter().all(|b| b.pressed))
+}
+
+/// Check mark over pressed buttons so they don't rely on red vs green
+fn draw_pressed_mark(button: &Button) {
+    let center = (button.range.0 + button.range.1) / 2.0;
+    draw_circle(center.x, center.y, 34.0, Color::new(0.0, 0.0, 0.0, 0.6));
+    draw_line(
+        center.x - 18.0,
+        center.y,
+        center.x - 4.0,
+        center.y + 16.0,
+        8.0,
+        WHITE,
+    );
+    draw_line(
+        center.x - 4.0,
+        center.y + 16.0,
+        center.x + 20.0,
+        center.y - 16.0,
+        8.0,
+        WHITE,
+    );
+}
+
+pub fn bot_action(data: &Data) -> BotAction {
+    match data
+        .buttons
         .iter()
-        .map(|b| b.pressed)
-        .reduce(|acc, e| acc && e)
-        .unwrap()
+        .find(|b| !b.pressed && b.order == data.button_on)
+    {
+        Some(b) => BotAction::Click((b.range.0 + b.range.1) / 2.0),
+        None => BotAction::Idle,
+    }
 }
 
-pub fn draw(data: &Data, textures: &HashMap<&str, Texture2D>) {
+#[cfg(feature = "dev")]
+pub fn hitboxes(data: &Data) -> Vec<Hitbox> {
+    data.buttons.iter().map(|b| Hitbox::Rect(b.range)).collect()
+}
+
+pub fn draw(data: &Data, textures: &HashMap<&str, Texture2D>, access: &Accessibility) {
     clear_background(WHITE);
     draw_texture(textures.get("combo_bkgd").unwrap(), 0.0, 0.0, WHITE);
 
@@ -98,5 +145,67 @@ pub fn draw(data: &Data, textures: &HashMap<&str, Texture2D>) {
             )
             .unwrap();
         draw_texture(tex, button.range.0.x, button.range.0.y, WHITE);
+        if access.colorblind && button.pressed {
+            draw_pressed_mark(button);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::microgames::test_input::{click, hover, FRAME};
+
+    fn press(data: &mut Data, order: u8) -> bool {
+        let i = data.buttons.iter().position(|b| b.order == order).unwrap();
+        let center = (data.buttons[i].range.0 + data.buttons[i].range.1) / 2.0;
+        update(data, click(center.x, center.y), FRAME, &mut vec![]).is_won()
+    }
+
+    #[test]
+    fn pressing_in_order_wins() {
+        let mut data = Data::new(&Rng::new(1));
+        assert!(!press(&mut data, 0));
+        assert!(!press(&mut data, 1));
+        assert!(!press(&mut data, 2));
+        assert!(press(&mut data, 3));
+    }
+
+    #[test]
+    fn wrong_press_resets_the_combo() {
+        let mut data = Data::new(&Rng::new(2));
+        press(&mut data, 0);
+        press(&mut data, 1);
+        press(&mut data, 3);
+        assert_eq!(data.button_on, 0);
+        assert!(data.buttons.iter().all(|b| !b.pressed));
+    }
+
+    #[test]
+    fn presses_are_reported() {
+        let mut data = Data::new(&Rng::new(5));
+        let mut events = vec![];
+        for order in [0, 2] {
+            let i = data.buttons.iter().position(|b| b.order == order).unwrap();
+            let center = (data.buttons[i].range.0 + data.buttons[i].range.1) / 2.0;
+            update(&mut data, click(center.x, center.y), FRAME, &mut events);
+        }
+        assert_eq!(events, [GameEvents::ButtonPressed, GameEvents::WrongInput]);
+    }
+
+    #[test]
+    fn repressing_a_lit_button_is_ignored() {
+        let mut data = Data::new(&Rng::new(3));
+        press(&mut data, 0);
+        press(&mut data, 0);
+        assert_eq!(data.button_on, 1);
+    }
+
+    #[test]
+    fn hover_does_not_press() {
+        let mut data = Data::new(&Rng::new(4));
+        let center = (data.buttons[0].range.0 + data.buttons[0].range.1) / 2.0;
+        update(&mut data, hover(center.x, center.y), FRAME, &mut vec![]);
+        assert_eq!(data.button_on, 0);
     }
 }
@@ -1,8 +1,13 @@
 use std::collections::HashMap;
 
-use crate::game::{FrameInput, MousePressState};
+#[cfg(feature = "dev")]
+use crate::game::dev::Hitbox;
+use crate::game::{
+    bot::BotAction, microgames::MicrogameStatus, rng::Rng, FrameInput, GameEvents, MousePressState,
+};
 use macroquad::prelude::*;
-use rand::{gen_range, ChooseRandom};
+
+pub const PROMPT: &str = "prompt.course";
 
 pub struct Data {
     hovering_right: bool,
@@ -10,10 +15,10 @@ pub struct Data {
     course: i8,
 }
 
-impl Default for Data {
-    fn default() -> Self {
+impl Data {
+    pub fn new(rng: &Rng) -> Self {
         let mut vals: Vec<i8> = vec![0, 1, 3, 4];
-        vals.shuffle();
+        rng.shuffle(&mut vals);
         Data {
             hovering_left: false,
             hovering_right: false,
@@ -22,7 +27,12 @@ impl Default for Data {
     }
 }
 
-pub fn update(data: &mut Data, input: FrameInput, delta: f32) -> bool {
+pub fn update(
+    data: &mut Data,
+    input: FrameInput,
+    delta: f32,
+    events: &mut Vec<GameEvents>,
+) -> MicrogameStatus {
     data.hovering_right = (input.mouse_position.x > 100.0
         && input.mouse_position.x < 350.0
         && input.mouse_position.y > 100.0
@@ -42,7 +52,23 @@ pub fn update(data: &mut Data, input: FrameInput, delta: f32) -> bool {
         data.course = clamp(data.course, 0, 4);
     }
 
-    data.course == 2
+    MicrogameStatus::won_while(data.course == 2)
+}
+
+pub fn bot_action(data: &Data) -> BotAction {
+    match data.course.cmp(&2) {
+        std::cmp::Ordering::Less => BotAction::Click(Vec2::new(225.0, 185.0)),
+        std::cmp::Ordering::Greater => BotAction::Click(Vec2::new(225.0, 420.0)),
+        std::cmp::Ordering::Equal => BotAction::Idle,
+    }
+}
+
+#[cfg(feature = "dev")]
+pub fn hitboxes(data: &Data) -> Vec<Hitbox> {
+    vec![
+        Hitbox::Rect((Vec2::new(100.0, 100.0), Vec2::new(350.0, 270.0))),
+        Hitbox::Rect((Vec2::new(100.0, 330.0), Vec2::new(350.0, 510.0))),
+    ]
 }
 
 pub fn draw(data: &Data, textures: &HashMap<&str, Texture2D>) {
@@ -57,3 +83,47 @@ pub fn draw(data: &Data, textures: &HashMap<&str, Texture2D>) {
         WHITE,
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::microgames::test_input::{click, FRAME};
+
+    const RIGHT: (f32, f32) = (225.0, 185.0);
+    const LEFT: (f32, f32) = (225.0, 420.0);
+
+    fn with_course(course: i8) -> Data {
+        Data {
+            course,
+            ..Data::new(&Rng::new(0))
+        }
+    }
+
+    #[test]
+    fn never_starts_on_course() {
+        for seed in 0..50 {
+            assert_ne!(Data::new(&Rng::new(seed)).course, 2);
+        }
+    }
+
+    #[test]
+    fn course_clamps_to_the_dial() {
+        let mut data = with_course(4);
+        update(&mut data, click(RIGHT.0, RIGHT.1), FRAME, &mut vec![]);
+        assert_eq!(data.course, 4);
+        let mut data = with_course(0);
+        update(&mut data, click(LEFT.0, LEFT.1), FRAME, &mut vec![]);
+        assert_eq!(data.course, 0);
+    }
+
+    #[test]
+    fn wins_only_at_two() {
+        let mut data = with_course(1);
+        let mut steer = |x, y| update(&mut data, click(x, y), FRAME, &mut vec![]);
+        assert_eq!(steer(RIGHT.0, RIGHT.1), MicrogameStatus::Won);
+        // Overshooting is still recoverable
+        assert_eq!(steer(RIGHT.0, RIGHT.1), MicrogameStatus::Running);
+        assert_eq!(steer(LEFT.0, LEFT.1), MicrogameStatus::Won);
+        assert_eq!(data.course, 2);
+    }
+}
@@ -1,8 +1,17 @@
 use std::collections::HashMap;
 
-use crate::game::{FrameInput, MousePressState};
+#[cfg(feature = "dev")]
+use crate::game::dev::Hitbox;
+use crate::game::{
+    bot::BotAction,
+    microgames::MicrogameStatus,
+    tween::{Ease, Lerp},
+    FrameInput, GameEvents, MousePressState,
+};
 use macroquad::prelude::*;
 
+pub const PROMPT: &str = "prompt.crank";
+
 pub struct Data {
     angle: f32,
     is_draging: bool,
@@ -19,8 +28,14 @@ impl Default for Data {
 
 const CRANK_CENTER: Vec2 = Vec2::new(100.0, 700.0);
 const CRANK_LENGTH: f32 = 380.0;
-
-pub fn update(data: &mut Data, input: FrameInput, delta: f32) -> bool {
+const ASSIST_SPEED: f32 = 1.0;
+
+pub fn update(
+    data: &mut Data,
+    input: FrameInput,
+    delta: f32,
+    events: &mut Vec<GameEvents>,
+) -> MicrogameStatus {
     let crank_off = Vec2::from_angle(data.angle) * CRANK_LENGTH;
 
     let player_mouse_dist = input.mouse_position.distance(crank_off + CRANK_CENTER);
@@ -37,19 +52,41 @@ pub fn update(data: &mut Data, input: FrameInput, delta: f32) -> bool {
 
     if data.is_draging {
         data.angle += clamp(angle_to_mouse, 0.0, delta * 1.0);
+    } else if input.assist {
+        data.angle += delta * ASSIST_SPEED;
     }
     data.angle = data.angle.clamp(-1.5, -0.5);
 
-    data.angle >= -0.5
+    // The crank only turns one way, so it can't be undone
+    MicrogameStatus::done_when(data.angle >= -0.5)
+}
+
+pub fn bot_action(data: &Data) -> BotAction {
+    if data.angle >= -0.5 {
+        BotAction::Idle
+    } else if data.is_draging {
+        // Lead the handle slightly so it keeps turning
+        BotAction::Hold(Vec2::from_angle(data.angle + 0.1) * CRANK_LENGTH + CRANK_CENTER)
+    } else {
+        BotAction::Click(Vec2::from_angle(data.angle) * CRANK_LENGTH + CRANK_CENTER)
+    }
+}
+
+/// Grab radius and the wider radius the handle can be held in
+#[cfg(feature = "dev")]
+pub fn hitboxes(data: &Data) -> Vec<Hitbox> {
+    let handle = Vec2::from_angle(data.angle) * CRANK_LENGTH + CRANK_CENTER;
+    vec![Hitbox::Circle(handle, 50.0), Hitbox::Circle(handle, 80.0)]
 }
 
 pub fn draw(data: &Data, textures: &HashMap<&str, Texture2D>) {
     clear_background(BLACK);
 
+    let door_progress = (data.angle + 1.5) / 1.0;
     draw_texture(
         textures.get("crank_door").unwrap(),
         496.0,
-        164.0 - 200.0 * ((data.angle + 1.5) / 1.0),
+        Lerp::lerp(164.0, -36.0, Ease::OutQuad.apply(door_progress)),
         WHITE,
     );
     draw_texture(textures.get("crank_bkgd").unwrap(), 0.0, 0.0, WHITE);
@@ -69,17 +106,65 @@ pub fn draw(data: &Data, textures: &HashMap<&str, Texture2D>) {
         WHITE,
         crank_draw_params,
     );
-    /*draw_circle(
-        crank_off.x + CRANK_CENTER.x,
-        crank_off.y + CRANK_CENTER.y,
-        50.0,
-        if data.is_draging { GREEN } else { RED },
-    );
-    draw_text(
-        format!("{}", data.angle).as_str(),
-        CRANK_CENTER.x,
-        CRANK_CENTER.y,
-        32.0,
-        BLACK,
-    );*/
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::microgames::test_input::{click, hold, FRAME};
+
+    fn handle_at(angle: f32) -> Vec2 {
+        CRANK_CENTER + Vec2::from_angle(angle) * CRANK_LENGTH
+    }
+
+    fn grabbed_at(angle: f32) -> Data {
+        let mut data = Data {
+            angle,
+            is_draging: false,
+        };
+        let h = handle_at(angle);
+        update(&mut data, click(h.x, h.y), FRAME, &mut vec![]);
+        assert!(data.is_draging);
+        data
+    }
+
+    #[test]
+    fn cannot_crank_backwards() {
+        let mut data = grabbed_at(-1.0);
+        let back = handle_at(-1.1);
+        update(&mut data, hold(back.x, back.y), FRAME, &mut vec![]);
+        assert_eq!(data.angle, -1.0);
+    }
+
+    #[test]
+    fn forward_drag_is_rate_limited() {
+        let mut data = grabbed_at(-1.0);
+        let ahead = handle_at(-0.95);
+        update(&mut data, hold(ahead.x, ahead.y), FRAME, &mut vec![]);
+        assert!(data.angle > -1.0);
+        assert!(data.angle <= -1.0 + FRAME + f32::EPSILON);
+    }
+
+    #[test]
+    fn wins_once_fully_cranked() {
+        let mut data = Data {
+            angle: -0.5,
+            is_draging: false,
+        };
+        assert_eq!(
+            update(&mut data, hold(0.0, 0.0), FRAME, &mut vec![]),
+            MicrogameStatus::WonEarly
+        );
+    }
+
+    #[test]
+    fn assist_turns_the_crank() {
+        let mut data = Data::default();
+        let input = FrameInput {
+            assist: true,
+            ..Default::default()
+        };
+        update(&mut data, input, FRAME, &mut vec![]);
+        assert!(data.angle > -1.5);
+    }
 }
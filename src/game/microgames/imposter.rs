@@ -1,8 +1,14 @@
 use std::collections::HashMap;
 
-use crate::game::{FrameInput, MousePressState};
+#[cfg(feature = "dev")]
+use crate::game::dev::Hitbox;
+use crate::game::{
+    bot::BotAction, microgames::MicrogameStatus, rng::Rng, vec2_in_range, FrameInput, GameEvents,
+    MousePressState,
+};
 use macroquad::prelude::*;
-use rand::gen_range;
+
+pub const PROMPT: &str = "prompt.imposter";
 
 pub struct Data {
     imposter_slot: u8,
@@ -10,10 +16,10 @@ pub struct Data {
     imposter_selected: i8,
 }
 
-impl Default for Data {
-    fn default() -> Self {
+impl Data {
+    pub fn new(rng: &Rng) -> Self {
         Data {
-            imposter_slot: gen_range(0, 4),
+            imposter_slot: rng.gen_range(0, 4),
             imposter_hilighted: -1,
             imposter_selected: -1,
         }
@@ -27,14 +33,15 @@ const SEL_AREAS: [(Vec2, Vec2); 4] = [
     (Vec2::new(800.0, 100.0), Vec2::new(950.0, 650.0)),
 ];
 
-pub fn update(data: &mut Data, input: FrameInput, delta: f32) -> bool {
+pub fn update(
+    data: &mut Data,
+    input: FrameInput,
+    delta: f32,
+    events: &mut Vec<GameEvents>,
+) -> MicrogameStatus {
     let mut is_sel = false;
-    for i in 0..4 {
-        if (input.mouse_position.x > SEL_AREAS[i].0.x
-            && input.mouse_position.x < SEL_AREAS[i].1.x
-            && input.mouse_position.y > SEL_AREAS[i].0.y
-            && input.mouse_position.y < SEL_AREAS[i].1.y)
-        {
+    for (i, area) in SEL_AREAS.iter().enumerate() {
+        if vec2_in_range(&input.mouse_position, area) {
             is_sel = true;
             data.imposter_hilighted = i as i8;
         }
@@ -43,14 +50,35 @@ pub fn update(data: &mut Data, input: FrameInput, delta: f32) -> bool {
         data.imposter_hilighted = -1;
     }
 
+    // No taking it back once someone's been picked
     if input.mouse_state == MousePressState::JustPressed
         && data.imposter_hilighted != -1
-        && data.imposter_hilighted != -1
+        && data.imposter_selected == -1
     {
         data.imposter_selected = data.imposter_hilighted;
+        if data.imposter_selected != data.imposter_slot as i8 {
+            events.push(GameEvents::WrongInput);
+        }
+    }
+
+    match data.imposter_selected {
+        -1 => MicrogameStatus::Running,
+        s if s == data.imposter_slot as i8 => MicrogameStatus::WonEarly,
+        _ => MicrogameStatus::Lost,
+    }
+}
+
+pub fn bot_action(data: &Data) -> BotAction {
+    if data.imposter_selected == data.imposter_slot as i8 {
+        return BotAction::Idle;
     }
+    let area = SEL_AREAS[data.imposter_slot as usize];
+    BotAction::Click(Vec2::new((area.0.x + area.1.x) / 2.0, 375.0))
+}
 
-    data.imposter_selected == data.imposter_slot as i8
+#[cfg(feature = "dev")]
+pub fn hitboxes(data: &Data) -> Vec<Hitbox> {
+    SEL_AREAS.iter().map(|a| Hitbox::Rect(*a)).collect()
 }
 
 pub fn draw(data: &Data, textures: &HashMap<&str, Texture2D>) {
@@ -71,3 +99,66 @@ pub fn draw(data: &Data, textures: &HashMap<&str, Texture2D>) {
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::microgames::test_input::{click, hover, FRAME};
+
+    fn center(slot: usize) -> Vec2 {
+        (SEL_AREAS[slot].0 + SEL_AREAS[slot].1) / 2.0
+    }
+
+    fn with_slot(imposter_slot: u8) -> Data {
+        Data {
+            imposter_slot,
+            ..Data::new(&Rng::new(0))
+        }
+    }
+
+    #[test]
+    fn wins_only_on_the_imposter_slot() {
+        for slot in 0..4 {
+            let mut data = with_slot(2);
+            let c = center(slot);
+            assert_eq!(
+                update(&mut data, click(c.x, c.y), FRAME, &mut vec![]),
+                if slot == 2 {
+                    MicrogameStatus::WonEarly
+                } else {
+                    MicrogameStatus::Lost
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn picking_a_human_is_reported() {
+        let mut data = with_slot(0);
+        let mut events = vec![];
+        let c = center(1);
+        update(&mut data, click(c.x, c.y), FRAME, &mut events);
+        assert_eq!(events, [GameEvents::WrongInput]);
+    }
+
+    #[test]
+    fn hovering_highlights_without_picking() {
+        let mut data = with_slot(1);
+        let c = center(1);
+        assert_eq!(
+            update(&mut data, hover(c.x, c.y), FRAME, &mut vec![]),
+            MicrogameStatus::Running
+        );
+        assert_eq!(data.imposter_hilighted, 1);
+        assert_eq!(data.imposter_selected, -1);
+    }
+
+    #[test]
+    fn wrong_pick_is_final() {
+        let mut data = with_slot(3);
+        let (wrong, right) = (center(0), center(3));
+        let mut pick = |c: Vec2| update(&mut data, click(c.x, c.y), FRAME, &mut vec![]);
+        assert_eq!(pick(wrong), MicrogameStatus::Lost);
+        assert_eq!(pick(right), MicrogameStatus::Lost);
+    }
+}
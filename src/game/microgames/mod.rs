@@ -1,7 +1,17 @@
-use macroquad::{prelude::*, rand::gen_range};
-use rand::ChooseRandom;
+// Every microgame update takes the event list, even ones that never push to it
+#![allow(clippy::ptr_arg)]
+use std::collections::HashMap;
+
+use macroquad::prelude::*;
+
+#[cfg(feature = "dev")]
+use super::dev::Hitbox;
+use super::{bot::BotAction, rng::Rng, settings::Accessibility, FrameInput, GameEvents};
+
 pub mod always_win;
 pub mod asteroids;
+pub mod boss_asteroid;
+pub mod boss_pipes;
 pub mod combo;
 pub mod course;
 pub mod crank;
@@ -10,6 +20,49 @@ pub mod pipes;
 pub mod swap;
 pub mod sweep;
 
+/// Where a microgame stands after a frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MicrogameStatus {
+    /// Not won, but there's still time to turn it around
+    Running,
+    /// Won for now, though it could still be undone before the timer runs out
+    Won,
+    /// Lost for good, nothing left to do
+    Lost,
+    /// Won for good, the rest of the timer can be skipped
+    WonEarly,
+}
+
+impl MicrogameStatus {
+    /// For microgames whose win can be undone again
+    pub fn won_while(won: bool) -> Self {
+        if won {
+            MicrogameStatus::Won
+        } else {
+            MicrogameStatus::Running
+        }
+    }
+
+    /// For microgames that stay won once they're done
+    pub fn done_when(done: bool) -> Self {
+        if done {
+            MicrogameStatus::WonEarly
+        } else {
+            MicrogameStatus::Running
+        }
+    }
+
+    pub fn is_won(self) -> bool {
+        matches!(self, MicrogameStatus::Won | MicrogameStatus::WonEarly)
+    }
+
+    /// Whether the outcome is settled and can't change any more
+    pub fn is_decided(self) -> bool {
+        matches!(self, MicrogameStatus::Lost | MicrogameStatus::WonEarly)
+    }
+}
+
+#[allow(clippy::large_enum_variant)]
 pub enum Microgames {
     Dummy,
     AlwaysWin(always_win::Data),
@@ -21,49 +74,239 @@ pub enum Microgames {
     Imposter(imposter::Data),
     Crank(crank::Data),
     Swap(swap::Data),
+    BossAsteroid(boss_asteroid::Data),
+    BossPipes(boss_pipes::Data),
+}
+
+impl Microgames {
+    /// Steps the microgame, returning where it stands
+    pub fn update(
+        &mut self,
+        input: FrameInput,
+        delta: f32,
+        events: &mut Vec<GameEvents>,
+    ) -> MicrogameStatus {
+        match self {
+            Microgames::AlwaysWin(d) => always_win::update(d, input, delta, events),
+            Microgames::Pipes(d) => pipes::update(d, input, delta, events),
+            Microgames::Combo(d) => combo::update(d, input, delta, events),
+            Microgames::Course(d) => course::update(d, input, delta, events),
+            Microgames::Sweep(d) => sweep::update(d, input, delta, events),
+            Microgames::Asteroids(d) => asteroids::update(d, input, delta, events),
+            Microgames::Imposter(d) => imposter::update(d, input, delta, events),
+            Microgames::Swap(d) => swap::update(d, input, delta, events),
+            Microgames::Crank(d) => crank::update(d, input, delta, events),
+            Microgames::BossAsteroid(d) => boss_asteroid::update(d, input, delta, events),
+            Microgames::BossPipes(d) => boss_pipes::update(d, input, delta, events),
+            Microgames::Dummy => MicrogameStatus::Won,
+        }
+    }
+
+    pub fn draw(&self, textures: &HashMap<&str, Texture2D>, access: &Accessibility) {
+        match self {
+            Microgames::AlwaysWin(d) => always_win::draw(d),
+            Microgames::Pipes(d) => pipes::draw(d, textures),
+            Microgames::Combo(d) => combo::draw(d, textures, access),
+            Microgames::Course(d) => course::draw(d, textures),
+            Microgames::Sweep(d) => sweep::draw(d, textures),
+            Microgames::Asteroids(d) => asteroids::draw(d, textures, access),
+            Microgames::Imposter(d) => imposter::draw(d, textures),
+            Microgames::Swap(d) => swap::draw(d, textures),
+            Microgames::Crank(d) => crank::draw(d, textures),
+            Microgames::BossAsteroid(d) => boss_asteroid::draw(d, textures, access),
+            Microgames::BossPipes(d) => boss_pipes::draw(d, textures),
+            Microgames::Dummy => {}
+        }
+    }
+
+    /// Texture shown dropping past the doors as the microgame comes in
+    pub fn plate_key(&self) -> &'static str {
+        plate_key_of(self.name())
+    }
+
+    /// What a bot player would do next to solve the microgame
+    pub fn bot_action(&self) -> BotAction {
+        match self {
+            Microgames::Pipes(d) => pipes::bot_action(d),
+            Microgames::Combo(d) => combo::bot_action(d),
+            Microgames::Course(d) => course::bot_action(d),
+            Microgames::Sweep(d) => sweep::bot_action(d),
+            Microgames::Asteroids(d) => asteroids::bot_action(d),
+            Microgames::Imposter(d) => imposter::bot_action(d),
+            Microgames::Swap(d) => swap::bot_action(d),
+            Microgames::Crank(d) => crank::bot_action(d),
+            Microgames::BossAsteroid(d) => boss_asteroid::bot_action(d),
+            Microgames::BossPipes(d) => boss_pipes::bot_action(d),
+            Microgames::Dummy | Microgames::AlwaysWin(_) => BotAction::Idle,
+        }
+    }
+
+    /// Short identifier used in reports and logs
+    pub fn name(&self) -> &'static str {
+        match self {
+            Microgames::Dummy => "dummy",
+            Microgames::AlwaysWin(_) => "always_win",
+            Microgames::Pipes(_) => "pipes",
+            Microgames::Combo(_) => "combo",
+            Microgames::Course(_) => "course",
+            Microgames::Sweep(_) => "sweep",
+            Microgames::Asteroids(_) => "asteroids",
+            Microgames::Imposter(_) => "imposter",
+            Microgames::Crank(_) => "crank",
+            Microgames::Swap(_) => "swap",
+            Microgames::BossAsteroid(_) => "boss_asteroid",
+            Microgames::BossPipes(_) => "boss_pipes",
+        }
+    }
+
+    /// Areas the microgame reacts to, for the dev overlay
+    #[cfg(feature = "dev")]
+    pub fn hitboxes(&self) -> Vec<Hitbox> {
+        match self {
+            Microgames::Pipes(d) => pipes::hitboxes(d),
+            Microgames::Combo(d) => combo::hitboxes(d),
+            Microgames::Course(d) => course::hitboxes(d),
+            Microgames::Sweep(d) => sweep::hitboxes(d),
+            Microgames::Asteroids(d) => asteroids::hitboxes(d),
+            Microgames::Imposter(d) => imposter::hitboxes(d),
+            Microgames::Swap(d) => swap::hitboxes(d),
+            Microgames::Crank(d) => crank::hitboxes(d),
+            Microgames::BossAsteroid(d) => boss_asteroid::hitboxes(d),
+            Microgames::BossPipes(d) => boss_pipes::hitboxes(d),
+            Microgames::Dummy | Microgames::AlwaysWin(_) => vec![],
+        }
+    }
+
+    /// String table key for the short instruction shown as the microgame starts
+    pub fn prompt_key(&self) -> &'static str {
+        match self {
+            Microgames::Dummy | Microgames::AlwaysWin(_) => always_win::PROMPT,
+            Microgames::Pipes(_) => pipes::PROMPT,
+            Microgames::Combo(_) => combo::PROMPT,
+            Microgames::Course(_) => course::PROMPT,
+            Microgames::Sweep(_) => sweep::PROMPT,
+            Microgames::Asteroids(_) => asteroids::PROMPT,
+            Microgames::Imposter(_) => imposter::PROMPT,
+            Microgames::Crank(_) => crank::PROMPT,
+            Microgames::Swap(_) => swap::PROMPT,
+            Microgames::BossAsteroid(_) => boss_asteroid::PROMPT,
+            Microgames::BossPipes(_) => boss_pipes::PROMPT,
+        }
+    }
+
+    pub fn is_boss(&self) -> bool {
+        matches!(self, Microgames::BossAsteroid(_) | Microgames::BossPipes(_))
+    }
+
+    /// Timer the microgame always gets, instead of the run's current tier
+    pub fn fixed_time(&self) -> Option<f32> {
+        match self {
+            Microgames::BossAsteroid(_) => Some(boss_asteroid::TIME),
+            Microgames::BossPipes(_) => Some(boss_pipes::TIME),
+            _ => None,
+        }
+    }
+
+    /// Phases a boss has got through, always 0 for regular microgames
+    pub fn phase(&self) -> u8 {
+        match self {
+            Microgames::BossAsteroid(d) => boss_asteroid::phase(d),
+            Microgames::BossPipes(d) => boss_pipes::phase(d),
+            _ => 0,
+        }
+    }
 }
 
-pub fn gen_new_microgame() -> Microgames {
+/// Short identifier for a microgame id, matching `Microgames::name`
+pub fn name_of(game_id: u8) -> &'static str {
+    match game_id {
+        0 => "pipes",
+        1 => "combo",
+        2 => "course",
+        3 => "sweep",
+        4 => "asteroids",
+        5 => "imposter",
+        6 => "crank",
+        7 => "swap",
+        8 => "boss_asteroid",
+        9 => "boss_pipes",
+        _ => "always_win",
+    }
+}
+
+pub fn id_from_name(name: &str) -> Option<u8> {
+    (0..=10).find(|id| name_of(*id) == name)
+}
+
+pub fn gen_new_microgame(rng: &Rng) -> Microgames {
     //match gen_range(0, 5) {
     //    0 => Microgames::Pipes(pipes::Data::default()),
-    //    1 => Microgames::Combo(combo::Data::default()),
-    //    2 => Microgames::Course(course::Data::default()),
+    //    1 => Microgames::Combo(combo::Data::new(rng)),
+    //    2 => Microgames::Course(course::Data::new(rng)),
     //    3 => Microgames::Sweep(sweep::Data::default()),
-    //    4 => Microgames::Asteroids(asteroids::Data::default()),
+    //    4 => Microgames::Asteroids(asteroids::Data::new(rng)),
     //    _ => Microgames::AlwaysWin(always_win::Data {}),
     //}
-    return Microgames::Asteroids(asteroids::Data::default());
+    Microgames::Asteroids(asteroids::Data::new(rng))
+}
+
+/// Plate texture for the microgame reported as `name`, empty if it has none
+pub fn plate_key_of(name: &str) -> &'static str {
+    match name {
+        "swap" => "swap_plate",
+        "pipes" | "boss_pipes" => "pipes_plate",
+        "combo" => "combo_plate",
+        "sweep" => "sweep_plate",
+        "crank" => "crank_plate",
+        "course" => "course_plate",
+        "imposter" => "imposter_plate",
+        "asteroids" | "boss_asteroid" => "asteroids_plate",
+        _ => "",
+    }
 }
 
-pub fn gen_microgame(game_id: u8) -> Microgames {
+pub fn gen_microgame(game_id: u8, rng: &Rng) -> Microgames {
     //    return Microgames::Asteroids(asteroids::Data::default());
     match game_id {
         0 => Microgames::Pipes(pipes::Data::default()),
-        1 => Microgames::Combo(combo::Data::default()),
-        2 => Microgames::Course(course::Data::default()),
+        1 => Microgames::Combo(combo::Data::new(rng)),
+        2 => Microgames::Course(course::Data::new(rng)),
         3 => Microgames::Sweep(sweep::Data::default()),
-        4 => Microgames::Asteroids(asteroids::Data::default()),
-        5 => Microgames::Imposter(imposter::Data::default()),
+        4 => Microgames::Asteroids(asteroids::Data::new(rng)),
+        5 => Microgames::Imposter(imposter::Data::new(rng)),
         6 => Microgames::Crank(crank::Data::default()),
-        7 => Microgames::Swap(swap::Data::default()),
+        7 => Microgames::Swap(swap::Data::new(rng)),
+        8 => Microgames::BossAsteroid(boss_asteroid::Data::new(rng)),
+        9 => Microgames::BossPipes(boss_pipes::Data::new(rng)),
         _ => Microgames::AlwaysWin(always_win::Data {}),
     }
 }
 
-pub fn gen_microgame_queue() -> Vec<u8> {
-    let mut out: Vec<u8> = vec![];
-    let mut ran: Vec<u8> = (0..8).collect();
-    ran.shuffle();
-    for n in ran.iter().take(6) {
-        out.push(*n);
+/// Synthetic input for driving microgame updates in tests
+#[cfg(test)]
+pub mod test_input {
+    use super::*;
+    use crate::game::MousePressState;
+
+    pub const FRAME: f32 = 1.0 / 60.0;
+
+    fn at(x: f32, y: f32, mouse_state: MousePressState) -> FrameInput {
+        FrameInput {
+            mouse_position: Vec2::new(x, y),
+            mouse_state,
+            ..Default::default()
+        }
+    }
+
+    pub fn click(x: f32, y: f32) -> FrameInput {
+        at(x, y, MousePressState::JustPressed)
     }
-    ran.shuffle();
-    for n in ran.iter().take(6) {
-        out.push(*n);
+
+    pub fn hold(x: f32, y: f32) -> FrameInput {
+        at(x, y, MousePressState::Pressed)
     }
-    ran.shuffle();
-    for n in ran.iter().take(4) {
-        out.push(*n);
+
+    pub fn hover(x: f32, y: f32) -> FrameInput {
+        at(x, y, MousePressState::NotPressed)
     }
-    return out;
 }
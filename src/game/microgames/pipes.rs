@@ -1,29 +1,39 @@
 use std::collections::HashMap;
 
 use macroquad::prelude::*;
-use rand::gen_range;
 
-use crate::game::{particles_draw, particles_update, FrameInput, MousePressState, Particle};
+#[cfg(feature = "dev")]
+use crate::game::dev::Hitbox;
+
+use crate::game::{
+    bot::BotAction,
+    microgames::MicrogameStatus,
+    particles::{self, Emitter},
+    FrameInput, GameEvents, MousePressState,
+};
+
+pub const PROMPT: &str = "prompt.pipes";
 
 struct Target {
     range: (Vec2, Vec2),
     clicked: bool,
-    timer: f32,
+    leak: Emitter,
 }
 
 impl Target {
     fn new(min: Vec2, max: Vec2) -> Self {
+        let mut config = particles::pipes_leak();
+        config.spawn_area = (max - min) / 2.0 - Vec2::splat(50.0);
         Target {
             range: (min, max),
             clicked: false,
-            timer: gen_range(0.01, 0.05),
+            leak: Emitter::new((min + max) / 2.0, config),
         }
     }
 }
 
 pub struct Data {
     targets: Vec<Target>,
-    particles: Vec<Particle>,
 }
 
 impl Default for Data {
@@ -34,12 +44,16 @@ impl Default for Data {
                 Target::new(Vec2::new(38.0, 366.0), Vec2::new(382.0, 546.0)),
                 Target::new(Vec2::new(664.0, 306.0), Vec2::new(933.0, 501.0)),
             ],
-            particles: vec![],
         }
     }
 }
 
-pub fn update(data: &mut Data, input: FrameInput, delta: f32) -> bool {
+pub fn update(
+    data: &mut Data,
+    input: FrameInput,
+    delta: f32,
+    events: &mut Vec<GameEvents>,
+) -> MicrogameStatus {
     for t in data.targets.iter_mut() {
         if input.mouse_position.x > t.range.0.x
             && input.mouse_position.x < t.range.1.x
@@ -49,68 +63,32 @@ pub fn update(data: &mut Data, input: FrameInput, delta: f32) -> bool {
         {
             t.clicked = true;
         }
-        t.timer -= delta;
-        if t.timer <= 0.0 && !t.clicked {
-            t.timer = gen_range(0.05, 0.1);
-            data.particles.push(Particle {
-                position: Vec2::new(
-                    gen_range(t.range.0.x + 50.0, t.range.1.x - 50.0),
-                    gen_range(t.range.0.y + 50.0, t.range.1.y - 50.0),
-                ),
-                velocity: Vec2::new(gen_range(-10.0, 10.0), gen_range(40.0, 60.0)),
-                color: Color {
-                    r: 0.7,
-                    g: 0.8,
-                    b: 1.0,
-                    a: 1.0,
-                },
-                color_delta: Color {
-                    r: 0.0,
-                    g: 0.0,
-                    b: 0.0,
-                    a: -0.1,
-                },
-                rotation: 0.0,
-                ang_velocity: 1.0,
-                lifetime: 1.0,
-            });
-        }
+        t.leak.active = !t.clicked;
+        t.leak.update(delta);
     }
 
-    particles_update(&mut data.particles, &delta);
+    MicrogameStatus::done_when(data.targets.iter().all(|t| t.clicked))
+}
+
+pub fn bot_action(data: &Data) -> BotAction {
+    match data.targets.iter().find(|t| !t.clicked) {
+        Some(t) => BotAction::Click((t.range.0 + t.range.1) / 2.0),
+        None => BotAction::Idle,
+    }
+}
 
-    data.targets
-        .iter()
-        .map(|t| t.clicked)
-        .reduce(|e, acc| e && acc)
-        .unwrap()
+#[cfg(feature = "dev")]
+pub fn hitboxes(data: &Data) -> Vec<Hitbox> {
+    data.targets.iter().map(|t| Hitbox::Rect(t.range)).collect()
 }
 
 pub fn draw(data: &Data, tex_map: &HashMap<&str, Texture2D>) {
     clear_background(WHITE);
 
     draw_texture(tex_map.get("pipes_bkgd").unwrap(), 0.0, 0.0, WHITE);
-    particles_draw(&data.particles, tex_map.get("smoke_particle").unwrap());
-
-    /*for t in data.targets.iter() {
-        if t.clicked {
-            draw_rectangle(
-                t.range.0.x,
-                t.range.0.y,
-                t.range.1.x - t.range.0.x,
-                t.range.1.y - t.range.0.y,
-                GREEN,
-            );
-        } else {
-            draw_rectangle(
-                t.range.0.x,
-                t.range.0.y,
-                t.range.1.x - t.range.0.x,
-                t.range.1.y - t.range.0.y,
-                RED,
-            );
-        }
-    }*/
+    for t in data.targets.iter() {
+        t.leak.draw(tex_map);
+    }
 
     if data.targets[0].clicked {
         draw_texture(tex_map.get("pipes_patch_1").unwrap(), 276.0, 110.0, WHITE);
@@ -122,3 +100,42 @@ pub fn draw(data: &Data, tex_map: &HashMap<&str, Texture2D>) {
         draw_texture(tex_map.get("pipes_patch_3").unwrap(), 664.0, 306.0, WHITE);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::microgames::test_input::{click, hover, FRAME};
+
+    fn center(data: &Data, i: usize) -> Vec2 {
+        (data.targets[i].range.0 + data.targets[i].range.1) / 2.0
+    }
+
+    #[test]
+    fn wins_once_every_leak_is_clicked() {
+        let mut data = Data::default();
+        let mut events = vec![];
+        for i in 0..3 {
+            let c = center(&data, i);
+            let won = update(&mut data, click(c.x, c.y), FRAME, &mut events);
+            assert_eq!(won.is_decided(), i == 2);
+        }
+    }
+
+    #[test]
+    fn hovering_or_missing_fixes_nothing() {
+        let mut data = Data::default();
+        let c = center(&data, 0);
+        update(&mut data, hover(c.x, c.y), FRAME, &mut vec![]);
+        update(&mut data, click(5.0, 5.0), FRAME, &mut vec![]);
+        assert!(data.targets.iter().all(|t| !t.clicked));
+    }
+
+    #[test]
+    fn fixed_leaks_stop_spraying() {
+        let mut data = Data::default();
+        let c = center(&data, 1);
+        update(&mut data, click(c.x, c.y), FRAME, &mut vec![]);
+        assert!(!data.targets[1].leak.active);
+        assert!(data.targets[0].leak.active);
+    }
+}
@@ -1,18 +1,24 @@
-use std::{collections::HashMap, usize};
+use std::collections::HashMap;
 
-use crate::game::{vec2_in_range, FrameInput, MousePressState};
+#[cfg(feature = "dev")]
+use crate::game::dev::Hitbox;
+use crate::game::{
+    bot::BotAction, microgames::MicrogameStatus, rng::Rng, vec2_in_range, FrameInput, GameEvents,
+    MousePressState,
+};
 use macroquad::prelude::*;
-use rand::ChooseRandom;
+
+pub const PROMPT: &str = "prompt.swap";
 
 pub struct Data {
     order: Vec<u8>,
     selected: i8,
 }
 
-impl Default for Data {
-    fn default() -> Self {
+impl Data {
+    pub fn new(rng: &Rng) -> Self {
         let mut order: Vec<u8> = (0..3).collect();
-        order.shuffle();
+        rng.shuffle(&mut order);
         Data {
             order,
             selected: -1,
@@ -20,7 +26,12 @@ impl Default for Data {
     }
 }
 
-pub fn update(data: &mut Data, input: FrameInput, delta: f32) -> bool {
+pub fn update(
+    data: &mut Data,
+    input: FrameInput,
+    delta: f32,
+    events: &mut Vec<GameEvents>,
+) -> MicrogameStatus {
     let mut hovered: i8 = -1;
     let mut click_boxes: Vec<(Vec2, Vec2)> = data
         .order
@@ -34,7 +45,7 @@ pub fn update(data: &mut Data, input: FrameInput, delta: f32) -> bool {
         })
         .collect();
 
-    for (box_range, i) in click_boxes.iter().zip(0..click_boxes.len()) {
+    for (i, box_range) in click_boxes.iter().enumerate() {
         if vec2_in_range(&input.mouse_position, box_range) {
             hovered = i as i8;
             //println!("Setting hover to {hovered}");
@@ -45,20 +56,51 @@ pub fn update(data: &mut Data, input: FrameInput, delta: f32) -> bool {
         if hovered == data.selected {
             data.selected = -1;
         } else if data.selected != -1 {
-            let temp = data.order[data.selected as usize];
-            data.order[data.selected as usize] = data.order[hovered as usize];
-            data.order[hovered as usize] = temp;
+            data.order.swap(data.selected as usize, hovered as usize);
             data.selected = -1;
+            events.push(GameEvents::CanSwapped);
         } else {
             data.selected = hovered;
         }
     }
 
-    data.order[0] < data.order[1] && data.order[1] < data.order[2]
+    MicrogameStatus::won_while(data.order[0] < data.order[1] && data.order[1] < data.order[2])
 }
 
 const BOX_OFFSETS: [f32; 3] = [233.0, 152.0, 80.0];
 
+fn can_center(index: usize) -> Vec2 {
+    Vec2::new(175.0 + 310.0 * index as f32, 365.0)
+}
+
+pub fn bot_action(data: &Data) -> BotAction {
+    // Fill slots left to right, slot i wants the can of height i
+    let Some(slot) = (0..data.order.len()).find(|i| data.order[*i] != *i as u8) else {
+        return BotAction::Idle;
+    };
+    if data.selected == -1 {
+        BotAction::Click(can_center(slot))
+    } else if data.selected as usize == slot {
+        let from = data.order.iter().position(|h| *h == slot as u8).unwrap();
+        BotAction::Click(can_center(from))
+    } else {
+        // Picked up the wrong can, put it back down
+        BotAction::Click(can_center(data.selected as usize))
+    }
+}
+
+#[cfg(feature = "dev")]
+pub fn hitboxes(data: &Data) -> Vec<Hitbox> {
+    (0..3)
+        .map(|i| {
+            Hitbox::Rect((
+                Vec2::new(30.0 + i as f32 * 310.0, 80.0),
+                Vec2::new(320.0 + i as f32 * 310.0, 650.0),
+            ))
+        })
+        .collect()
+}
+
 pub fn draw(data: &Data, textures: &HashMap<&str, Texture2D>) {
     draw_texture(textures.get("swap_bkgd").unwrap(), 0.0, 0.0, WHITE);
     for (height, index) in data.order.iter().zip(0..3) {
@@ -72,3 +114,49 @@ pub fn draw(data: &Data, textures: &HashMap<&str, Texture2D>) {
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::microgames::test_input::{click, FRAME};
+
+    fn click_can(data: &mut Data, index: usize) -> bool {
+        let c = can_center(index);
+        update(data, click(c.x, c.y), FRAME, &mut vec![]).is_won()
+    }
+
+    #[test]
+    fn second_click_on_the_same_can_deselects() {
+        let mut data = Data::new(&Rng::new(0));
+        click_can(&mut data, 1);
+        assert_eq!(data.selected, 1);
+        click_can(&mut data, 1);
+        assert_eq!(data.selected, -1);
+    }
+
+    #[test]
+    fn clicking_another_can_swaps_them() {
+        let mut data = Data {
+            order: vec![2, 0, 1],
+            selected: -1,
+        };
+        let mut events = vec![];
+        for can in [0, 2] {
+            let c = can_center(can);
+            update(&mut data, click(c.x, c.y), FRAME, &mut events);
+        }
+        assert_eq!(data.order, vec![1, 0, 2]);
+        assert_eq!(events, [GameEvents::CanSwapped]);
+        assert_eq!(data.selected, -1);
+    }
+
+    #[test]
+    fn wins_only_when_sorted() {
+        let mut data = Data {
+            order: vec![1, 0, 2],
+            selected: -1,
+        };
+        assert!(!click_can(&mut data, 0));
+        assert!(click_can(&mut data, 1));
+    }
+}
@@ -1,12 +1,46 @@
 use std::collections::HashMap;
 
-use crate::game::{FrameInput, MousePressState};
+#[cfg(feature = "dev")]
+use crate::game::dev::Hitbox;
+use crate::game::{
+    bot::BotAction,
+    microgames::MicrogameStatus,
+    tween::{Ease, Tween},
+    FrameInput, GameEvents, MousePressState,
+};
 use macroquad::prelude::*;
 
+pub const PROMPT: &str = "prompt.sweep";
+
 pub struct Data {
     progress: f32,
     is_dragging: bool,
     drag_pos: f32,
+    frame: usize,
+    frame_time: f32,
+}
+
+const ASSIST_SPEED: f32 = 1.0;
+
+const FRAMES: [(&str, Vec2); 3] = [
+    ("sweep_frame_1", Vec2::new(217.0, 21.0)),
+    ("sweep_frame_2", Vec2::new(335.0, 25.0)),
+    ("sweep_frame_3", Vec2::new(294.0, 24.0)),
+];
+
+fn frame_for_progress(progress: f32) -> usize {
+    if progress >= 1.0 {
+        2
+    } else if progress >= 0.5 {
+        1
+    } else {
+        0
+    }
+}
+
+/// New sweep frame fading in over the previous one
+fn frame_fade() -> Tween<Color> {
+    Tween::new(Color::new(1.0, 1.0, 1.0, 0.0), WHITE, 0.2, Ease::OutQuad)
 }
 
 impl Default for Data {
@@ -15,11 +49,18 @@ impl Default for Data {
             progress: 0.0,
             is_dragging: false,
             drag_pos: 0.0,
+            frame: 0,
+            frame_time: 1.0,
         }
     }
 }
 
-pub fn update(data: &mut Data, input: FrameInput, delta: f32) -> bool {
+pub fn update(
+    data: &mut Data,
+    input: FrameInput,
+    delta: f32,
+    events: &mut Vec<GameEvents>,
+) -> MicrogameStatus {
     let left_handle_bound = 20.0 + 800.0 * data.progress;
 
     let mouse_on_handle = (input.mouse_position.x > left_handle_bound
@@ -45,23 +86,63 @@ pub fn update(data: &mut Data, input: FrameInput, delta: f32) -> bool {
     if data.is_dragging {
         data.progress += drag_delta;
         data.drag_pos = input.mouse_position.x;
+    } else if input.assist {
+        data.progress += delta * ASSIST_SPEED;
     }
 
     data.progress = clamp(data.progress, 0.0, 1.0);
 
-    data.progress >= 1.0
+    data.frame_time += delta;
+    let frame = frame_for_progress(data.progress);
+    if frame != data.frame {
+        data.frame = frame;
+        data.frame_time = 0.0;
+    }
+
+    MicrogameStatus::done_when(data.progress >= 1.0)
+}
+
+pub fn bot_action(data: &Data) -> BotAction {
+    let left_handle_bound = 20.0 + 800.0 * data.progress;
+    if data.progress >= 1.0 {
+        BotAction::Idle
+    } else if data.is_dragging {
+        // Keep pulling from the front edge of the handle
+        BotAction::Hold(Vec2::new(left_handle_bound + 95.0, 505.0))
+    } else {
+        BotAction::Click(Vec2::new(left_handle_bound + 50.0, 505.0))
+    }
+}
+
+#[cfg(feature = "dev")]
+pub fn hitboxes(data: &Data) -> Vec<Hitbox> {
+    let left_handle_bound = 20.0 + 800.0 * data.progress;
+    vec![Hitbox::Rect((
+        Vec2::new(left_handle_bound, 420.0),
+        Vec2::new(left_handle_bound + 100.0, 590.0),
+    ))]
 }
 
 pub fn draw(data: &Data, textures: &HashMap<&str, Texture2D>) {
     draw_texture(textures.get("sweep_bkgd").unwrap(), 0.0, 0.0, WHITE);
 
-    if data.progress >= 1.0 {
-        draw_texture(textures.get("sweep_frame_3").unwrap(), 294.0, 24.0, WHITE);
-    } else if data.progress >= 0.5 {
-        draw_texture(textures.get("sweep_frame_2").unwrap(), 335.0, 25.0, WHITE);
-    } else {
-        draw_texture(textures.get("sweep_frame_1").unwrap(), 217.0, 21.0, WHITE);
+    let fade = frame_fade();
+    if data.frame > 0 && !fade.is_finished(data.frame_time) {
+        let (prev_tex, prev_pos) = FRAMES[data.frame - 1];
+        draw_texture(
+            textures.get(prev_tex).unwrap(),
+            prev_pos.x,
+            prev_pos.y,
+            WHITE,
+        );
     }
+    let (tex, pos) = FRAMES[data.frame];
+    draw_texture(
+        textures.get(tex).unwrap(),
+        pos.x,
+        pos.y,
+        fade.sample(data.frame_time),
+    );
 
     let left_handle_bound = 20.0 + 800.0 * data.progress;
     draw_texture(
@@ -71,3 +152,49 @@ pub fn draw(data: &Data, textures: &HashMap<&str, Texture2D>) {
         WHITE,
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::microgames::test_input::{click, hold, FRAME};
+
+    /// Sweep halfway done with the handle grabbed at x 470
+    fn grabbed_halfway() -> Data {
+        let mut data = Data {
+            progress: 0.5,
+            ..Data::default()
+        };
+        update(&mut data, click(470.0, 505.0), FRAME, &mut vec![]);
+        assert!(data.is_dragging);
+        data
+    }
+
+    #[test]
+    fn cannot_sweep_backwards() {
+        let mut data = grabbed_halfway();
+        update(&mut data, hold(440.0, 505.0), FRAME, &mut vec![]);
+        assert_eq!(data.progress, 0.5);
+    }
+
+    #[test]
+    fn forward_drag_is_rate_limited() {
+        let mut data = grabbed_halfway();
+        update(&mut data, hold(510.0, 505.0), FRAME, &mut vec![]);
+        assert!(data.progress > 0.5);
+        assert!(data.progress <= 0.5 + FRAME + f32::EPSILON);
+    }
+
+    #[test]
+    fn letting_go_stops_the_drag() {
+        let mut data = grabbed_halfway();
+        update(&mut data, FrameInput::default(), FRAME, &mut vec![]);
+        assert!(!data.is_dragging);
+    }
+
+    #[test]
+    fn frames_follow_progress() {
+        assert_eq!(frame_for_progress(0.2), 0);
+        assert_eq!(frame_for_progress(0.5), 1);
+        assert_eq!(frame_for_progress(1.0), 2);
+    }
+}
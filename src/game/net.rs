@@ -0,0 +1,409 @@
+//! Online versus: the line based protocol, a non-blocking client and the relay
+//! server that pairs players up. Runs are fully seeded, so only the seed and each
+//! microgame's outcome ever cross the wire.
+
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, BufReader, ErrorKind, Read, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    str::FromStr,
+    sync::{
+        mpsc::{self, Receiver, TryRecvError},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use super::run_config::GameMode;
+
+/// Where the client looks for a relay unless `RELAY_ENV` says otherwise
+pub const DEFAULT_RELAY: &str = "127.0.0.1:7777";
+pub const RELAY_ENV: &str = "MICROSHIP_RELAY";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+/// A client that stops reading only holds up its own opponent's messages this long
+const WRITE_TIMEOUT: Duration = Duration::from_secs(2);
+pub const ROOM_CODE_LEN: usize = 4;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    /// Client wants a room for a run of `mode` with `seed`
+    Host {
+        mode: GameMode,
+        seed: u64,
+    },
+    Join {
+        room: String,
+    },
+    /// Server made a room, the code is for the host to pass on
+    Room {
+        room: String,
+    },
+    /// Server to both players, the run can begin
+    Start {
+        mode: GameMode,
+        seed: u64,
+    },
+    /// A microgame finished, passed on to the opponent
+    Result {
+        stage: u8,
+        won: bool,
+        /// Milliseconds it took to win, 0 if it was lost
+        time_ms: u32,
+        lives: u8,
+    },
+    /// Sender's run is over, passed on to the opponent
+    Done,
+    /// Server telling a player their opponent disconnected
+    Left,
+    Error {
+        reason: String,
+    },
+}
+
+impl Message {
+    /// One line on the wire, without the newline
+    pub fn encode(&self) -> String {
+        match self {
+            Message::Host { mode, seed } => format!("HOST {} {seed}", mode.code()),
+            Message::Join { room } => format!("JOIN {room}"),
+            Message::Room { room } => format!("ROOM {room}"),
+            Message::Start { mode, seed } => format!("START {} {seed}", mode.code()),
+            Message::Result {
+                stage,
+                won,
+                time_ms,
+                lives,
+            } => format!("RESULT {stage} {} {time_ms} {lives}", *won as u8),
+            Message::Done => "DONE".to_string(),
+            Message::Left => "LEFT".to_string(),
+            Message::Error { reason } => format!("ERROR {reason}"),
+        }
+    }
+
+    pub fn parse(line: &str) -> Result<Message, String> {
+        let line = line.trim();
+        let (name, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let mut args = rest.split_whitespace();
+        let mut next = |what: &str| args.next().ok_or(format!("{name} is missing {what}"));
+        let mode = |code: &str| GameMode::from_code(code).ok_or(format!("unknown mode {code}"));
+        match name {
+            "HOST" => Ok(Message::Host {
+                mode: mode(next("mode")?)?,
+                seed: number("seed", next("seed")?)?,
+            }),
+            "JOIN" => Ok(Message::Join {
+                room: next("room")?.to_string(),
+            }),
+            "ROOM" => Ok(Message::Room {
+                room: next("room")?.to_string(),
+            }),
+            "START" => Ok(Message::Start {
+                mode: mode(next("mode")?)?,
+                seed: number("seed", next("seed")?)?,
+            }),
+            "RESULT" => Ok(Message::Result {
+                stage: number("stage", next("stage")?)?,
+                won: next("outcome")? == "1",
+                time_ms: number("time", next("time")?)?,
+                lives: number("lives", next("lives")?)?,
+            }),
+            "DONE" => Ok(Message::Done),
+            "LEFT" => Ok(Message::Left),
+            "ERROR" => Ok(Message::Error {
+                reason: rest.to_string(),
+            }),
+            _ => Err(format!("unknown message {name}")),
+        }
+    }
+}
+
+fn number<T: FromStr>(what: &str, s: &str) -> Result<T, String> {
+    s.parse().map_err(|_| format!("bad {what} {s}"))
+}
+
+fn write_message(stream: &mut TcpStream, message: &Message) -> io::Result<()> {
+    stream.write_all(format!("{}\n", message.encode()).as_bytes())
+}
+
+/// Connection to the relay that never blocks the game loop once it's up
+pub struct Client {
+    stream: TcpStream,
+    buffer: Vec<u8>,
+}
+
+impl Client {
+    pub fn connect(addr: &str) -> io::Result<Client> {
+        let addr = addr
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(ErrorKind::NotFound, "relay address didn't resolve"))?;
+        let stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)?;
+        stream.set_nodelay(true)?;
+        stream.set_nonblocking(true)?;
+        Ok(Client {
+            stream,
+            buffer: vec![],
+        })
+    }
+
+    pub fn send(&mut self, message: &Message) -> io::Result<()> {
+        write_message(&mut self.stream, message)
+    }
+
+    /// Messages that have arrived since the last poll, errors once the relay is gone
+    pub fn poll(&mut self) -> io::Result<Vec<Message>> {
+        let mut chunk = [0; 512];
+        loop {
+            match self.stream.read(&mut chunk) {
+                Ok(0) => return Err(ErrorKind::UnexpectedEof.into()),
+                Ok(n) => self.buffer.extend_from_slice(&chunk[..n]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            }
+        }
+
+        let mut messages = vec![];
+        while let Some(end) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            // Anything garbled is dropped rather than ending the match
+            if let Ok(message) = Message::parse(&String::from_utf8_lossy(&line)) {
+                messages.push(message);
+            }
+        }
+        Ok(messages)
+    }
+}
+
+/// `Client::connect` on its own thread, so the game keeps drawing while it waits
+pub struct Connecting {
+    result: Receiver<io::Result<Client>>,
+}
+
+impl Connecting {
+    pub fn start(addr: String) -> Self {
+        let (sender, result) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = sender.send(Client::connect(&addr));
+        });
+        Connecting { result }
+    }
+
+    /// The connection or why it failed, None while it's still trying
+    pub fn poll(&self) -> Option<io::Result<Client>> {
+        match self.result.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(io::Error::other("connecting gave up"))),
+        }
+    }
+}
+
+/// Four letter code for the `n`th room, every n below 26^4 gets a different one
+fn room_code(n: u64) -> String {
+    // 7919 shares no factor with 26^4, so this walks every code before repeating
+    let mut x = n.wrapping_mul(7919) % 26u64.pow(ROOM_CODE_LEN as u32);
+    (0..ROOM_CODE_LEN)
+        .map(|_| {
+            let c = (b'A' + (x % 26) as u8) as char;
+            x /= 26;
+            c
+        })
+        .collect()
+}
+
+struct WaitingRoom {
+    host: u64,
+    mode: GameMode,
+    seed: u64,
+}
+
+#[derive(Default)]
+struct Relay {
+    next_id: u64,
+    rooms: HashMap<String, WaitingRoom>,
+    /// Every connection's write half
+    writers: HashMap<u64, TcpStream>,
+    /// Who each paired up connection is playing against
+    opponents: HashMap<u64, u64>,
+    /// Messages waiting to go out once the lock is let go
+    outbox: Vec<(u64, Message)>,
+}
+
+impl Relay {
+    fn send(&mut self, to: u64, message: &Message) {
+        self.outbox.push((to, message.clone()));
+    }
+
+    /// Everything queued up with a handle to write it to, so a slow client is
+    /// written to without holding up every other room
+    fn take_outbox(&mut self) -> Vec<(TcpStream, Message)> {
+        let outbox = std::mem::take(&mut self.outbox);
+        outbox
+            .into_iter()
+            .filter_map(|(to, message)| {
+                let stream = self.writers.get(&to)?.try_clone().ok()?;
+                Some((stream, message))
+            })
+            .collect()
+    }
+
+    fn handle(&mut self, from: u64, message: Message) {
+        match message {
+            Message::Host { mode, seed } => {
+                let room = room_code(from);
+                self.rooms.insert(
+                    room.clone(),
+                    WaitingRoom {
+                        host: from,
+                        mode,
+                        seed,
+                    },
+                );
+                self.send(from, &Message::Room { room });
+            }
+            Message::Join { room } => match self.rooms.remove(&room.to_uppercase()) {
+                Some(waiting) => {
+                    self.opponents.insert(waiting.host, from);
+                    self.opponents.insert(from, waiting.host);
+                    let start = Message::Start {
+                        mode: waiting.mode,
+                        seed: waiting.seed,
+                    };
+                    self.send(waiting.host, &start);
+                    self.send(from, &start);
+                }
+                None => self.send(
+                    from,
+                    &Message::Error {
+                        reason: format!("no room {room}"),
+                    },
+                ),
+            },
+            Message::Result { .. } | Message::Done => {
+                if let Some(opponent) = self.opponents.get(&from).copied() {
+                    self.send(opponent, &message);
+                }
+            }
+            _ => self.send(
+                from,
+                &Message::Error {
+                    reason: "unexpected message".to_string(),
+                },
+            ),
+        }
+    }
+
+    fn disconnect(&mut self, id: u64) {
+        self.writers.remove(&id);
+        self.rooms.retain(|_, r| r.host != id);
+        if let Some(opponent) = self.opponents.remove(&id) {
+            self.opponents.remove(&opponent);
+            self.send(opponent, &Message::Left);
+        }
+    }
+}
+
+fn deliver(outbox: Vec<(TcpStream, Message)>) {
+    for (mut stream, message) in outbox {
+        let _ = write_message(&mut stream, &message);
+    }
+}
+
+fn serve_client(stream: TcpStream, relay: Arc<Mutex<Relay>>) -> io::Result<()> {
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    let reader = BufReader::new(stream.try_clone()?);
+    let id = {
+        let mut relay = relay.lock().unwrap();
+        let id = relay.next_id;
+        relay.next_id += 1;
+        relay.writers.insert(id, stream);
+        id
+    };
+    for line in reader.lines() {
+        let Ok(line) = line else { break };
+        let outbox = {
+            let mut relay = relay.lock().unwrap();
+            match Message::parse(&line) {
+                Ok(message) => relay.handle(id, message),
+                Err(reason) => relay.send(id, &Message::Error { reason }),
+            }
+            relay.take_outbox()
+        };
+        deliver(outbox);
+    }
+    let outbox = {
+        let mut relay = relay.lock().unwrap();
+        relay.disconnect(id);
+        relay.take_outbox()
+    };
+    deliver(outbox);
+    Ok(())
+}
+
+/// Pairs hosts with joiners and passes results between them, one thread per client
+pub fn run_relay(listener: TcpListener) -> io::Result<()> {
+    let relay = Arc::new(Mutex::new(Relay::default()));
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        let _ = stream.set_nodelay(true);
+        let relay = relay.clone();
+        thread::spawn(move || serve_client(stream, relay));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_round_trip() {
+        let messages = [
+            Message::Host {
+                mode: GameMode::Marathon,
+                seed: u64::MAX,
+            },
+            Message::Join {
+                room: "QWER".to_string(),
+            },
+            Message::Room {
+                room: "QWER".to_string(),
+            },
+            Message::Start {
+                mode: GameMode::OneLife,
+                seed: 7,
+            },
+            Message::Result {
+                stage: 4,
+                won: true,
+                time_ms: 1830,
+                lives: 2,
+            },
+            Message::Done,
+            Message::Left,
+            Message::Error {
+                reason: "no room ABCD".to_string(),
+            },
+        ];
+        for message in messages {
+            assert_eq!(Message::parse(&message.encode()), Ok(message));
+        }
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        assert!(Message::parse("").is_err());
+        assert!(Message::parse("HOST").is_err());
+        assert!(Message::parse("HOST endless 4").is_err());
+        assert!(Message::parse("RESULT 1 1 soon 3").is_err());
+        assert!(Message::parse("HELLO").is_err());
+    }
+
+    #[test]
+    fn room_codes_dont_repeat() {
+        let codes: std::collections::HashSet<String> = (0..5000).map(room_code).collect();
+        assert_eq!(codes.len(), 5000);
+        assert!(codes.iter().all(|c| c.len() == ROOM_CODE_LEN));
+    }
+}
@@ -0,0 +1,339 @@
+use macroquad::prelude::*;
+
+use super::{
+    daily::Date,
+    net::{Client, Connecting, Message, DEFAULT_RELAY, RELAY_ENV, ROOM_CODE_LEN},
+    party::{PlayStyle, Players, Standing, PLAYER_COLORS},
+    rng::Rng,
+    run_config::GameMode,
+    ui::{draw_text_centered, Button},
+    ActiveState, FrameInput, GameEvents, GameState,
+};
+
+const HOST_BUTTON: Button = Button::new(380.0, 200.0, 200.0, 48.0);
+const JOIN_BUTTON: Button = Button::new(380.0, 270.0, 200.0, 48.0);
+const BACK_BUTTON: Button = Button::new(380.0, 520.0, 200.0, 48.0);
+
+#[derive(Debug, PartialEq)]
+enum LobbyStep {
+    Choosing,
+    /// Typing in the room code a friend passed on
+    Entering,
+    /// Reaching the relay, off the game loop since it can take a few seconds
+    Connecting,
+    /// Waiting on the relay for a room code, then on someone to join it
+    Hosting(Option<String>),
+    Joining,
+    Failed(String),
+}
+
+/// Hosting or joining an online versus match
+pub struct LobbyData {
+    step: LobbyStep,
+    code: String,
+    client: Option<Client>,
+    /// Connection under way, with what to send once it's up and the step after
+    connecting: Option<(Connecting, Message, LobbyStep)>,
+    mouse_pos: Vec2,
+}
+
+impl Default for LobbyData {
+    fn default() -> Self {
+        LobbyData {
+            step: LobbyStep::Choosing,
+            code: String::new(),
+            client: None,
+            connecting: None,
+            mouse_pos: Vec2::ZERO,
+        }
+    }
+}
+
+impl LobbyData {
+    /// Hands the connection over to the match once it starts
+    pub fn take_client(&mut self) -> Option<Client> {
+        self.client.take()
+    }
+
+    /// Starts connecting, `first` is sent once it's up and the lobby moves on to `next`
+    fn open(&mut self, first: Message, next: LobbyStep) {
+        let addr = std::env::var(RELAY_ENV).unwrap_or_else(|_| DEFAULT_RELAY.to_string());
+        self.connecting = Some((Connecting::start(addr), first, next));
+        self.step = LobbyStep::Connecting;
+    }
+
+    /// Picks up the connection once it's through
+    fn poll_connecting(&mut self) {
+        let Some(result) = self.connecting.as_ref().and_then(|(c, _, _)| c.poll()) else {
+            return;
+        };
+        let Some((_, first, next)) = self.connecting.take() else {
+            return;
+        };
+        match result.and_then(|mut c| c.send(&first).map(|_| c)) {
+            Ok(client) => {
+                self.client = Some(client);
+                self.step = next;
+            }
+            Err(e) => self.step = LobbyStep::Failed(e.to_string()),
+        }
+    }
+
+    /// Takes typed characters into the room code, joining on enter
+    fn enter_code(&mut self, typed: &str) {
+        for c in typed.chars() {
+            match c {
+                '\u{8}' => {
+                    self.code.pop();
+                }
+                '\n' if self.code.len() == ROOM_CODE_LEN => {
+                    let room = self.code.clone();
+                    self.open(Message::Join { room }, LobbyStep::Joining);
+                    return;
+                }
+                c if c.is_ascii_alphabetic() && self.code.len() < ROOM_CODE_LEN => {
+                    self.code.push(c.to_ascii_uppercase());
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+pub fn update(
+    game_data: &mut GameState,
+    input: FrameInput,
+    delta: f32,
+    events: &mut Vec<GameEvents>,
+) -> Result<(), ()> {
+    if let ActiveState::Lobby(l_data) = &mut game_data.active_state {
+        l_data.mouse_pos = input.mouse_position;
+        l_data.poll_connecting();
+
+        if let Some(client) = &mut l_data.client {
+            match client.poll() {
+                Ok(messages) => {
+                    for message in messages {
+                        match message {
+                            Message::Room { room } => l_data.step = LobbyStep::Hosting(Some(room)),
+                            Message::Start { mode, seed } => {
+                                events.push(GameEvents::StartOnline { mode, seed })
+                            }
+                            Message::Error { reason } => l_data.step = LobbyStep::Failed(reason),
+                            _ => {}
+                        }
+                    }
+                }
+                Err(e) => l_data.step = LobbyStep::Failed(e.to_string()),
+            }
+            if matches!(l_data.step, LobbyStep::Failed(_)) {
+                l_data.client = None;
+            }
+        }
+
+        match l_data.step {
+            LobbyStep::Choosing | LobbyStep::Failed(_) => {
+                if HOST_BUTTON.clicked(&input) {
+                    let mode = game_data.settings.mode;
+                    let seed = match mode {
+                        GameMode::Daily => Date::today().seed(),
+                        _ => Rng::from_global().seed(),
+                    };
+                    let host = Message::Host { mode, seed };
+                    l_data.open(host, LobbyStep::Hosting(None));
+                } else if JOIN_BUTTON.clicked(&input) {
+                    l_data.code.clear();
+                    l_data.step = LobbyStep::Entering;
+                }
+            }
+            LobbyStep::Entering => l_data.enter_code(&input.typed),
+            LobbyStep::Connecting | LobbyStep::Hosting(_) | LobbyStep::Joining => {}
+        }
+
+        if BACK_BUTTON.clicked(&input) {
+            events.push(GameEvents::MainMenuReturn);
+        }
+
+        Ok(())
+    } else {
+        Err(())
+    }
+}
+
+pub fn draw(game_data: &GameState) -> Result<(), ()> {
+    if let ActiveState::Lobby(l_data) = &game_data.active_state {
+        let strings = &game_data.strings;
+        clear_background(Color::new(0.08, 0.08, 0.12, 1.0));
+        draw_text_centered(
+            strings.get("online.title"),
+            Vec2::new(480.0, 80.0),
+            72.0,
+            WHITE,
+        );
+        draw_text_centered(
+            strings.get(game_data.settings.mode.name_key()),
+            Vec2::new(480.0, 140.0),
+            32.0,
+            GRAY,
+        );
+
+        let status = Vec2::new(480.0, 380.0);
+        match &l_data.step {
+            LobbyStep::Choosing => {}
+            LobbyStep::Entering => {
+                let blanks = "_".repeat(ROOM_CODE_LEN - l_data.code.len());
+                draw_text_centered(strings.get("online.enter_code"), status, 32.0, WHITE);
+                draw_text_centered(
+                    &format!("{}{blanks}", l_data.code),
+                    status + Vec2::new(0.0, 60.0),
+                    64.0,
+                    YELLOW,
+                );
+            }
+            LobbyStep::Hosting(room) => {
+                draw_text_centered(strings.get("online.waiting"), status, 32.0, WHITE);
+                if let Some(room) = room {
+                    draw_text_centered(room, status + Vec2::new(0.0, 60.0), 64.0, YELLOW);
+                }
+            }
+            LobbyStep::Connecting => {
+                draw_text_centered(strings.get("online.connecting"), status, 32.0, WHITE)
+            }
+            LobbyStep::Joining => {
+                draw_text_centered(strings.get("online.joining"), status, 32.0, WHITE)
+            }
+            LobbyStep::Failed(reason) => {
+                draw_text_centered(strings.get("online.failed"), status, 32.0, RED);
+                draw_text_centered(reason, status + Vec2::new(0.0, 40.0), 24.0, GRAY);
+            }
+        }
+
+        if matches!(l_data.step, LobbyStep::Choosing | LobbyStep::Failed(_)) {
+            HOST_BUTTON.draw(strings.get("online.host"), &l_data.mouse_pos);
+            JOIN_BUTTON.draw(strings.get("online.join"), &l_data.mouse_pos);
+        }
+        BACK_BUTTON.draw(strings.get("menu.back"), &l_data.mouse_pos);
+        Ok(())
+    } else {
+        Err(())
+    }
+}
+
+/// Both sides of an online run, player 1 is us and player 2 the opponent
+pub struct OnlineMatch {
+    /// None once the relay or the opponent is gone
+    client: Option<Client>,
+    players: Players,
+    run_length: u8,
+    opponent_stage: u8,
+    opponent_done: bool,
+}
+
+impl OnlineMatch {
+    pub fn new(client: Client, lives: u8, run_length: u8) -> Self {
+        OnlineMatch {
+            client: Some(client),
+            players: Players::new(PlayStyle::Versus(2), lives),
+            run_length,
+            opponent_stage: 0,
+            opponent_done: false,
+        }
+    }
+
+    /// Sends our side of this frame's events, `lives` being what we have left after them
+    pub fn send_events(&mut self, events: &[GameEvents], lives: u8) {
+        for event in events {
+            let message = match *event {
+                GameEvents::MicrogameEnded { stage, won_at, .. } => {
+                    let me = self.players.player_mut(0);
+                    me.lives = lives;
+                    if let Some(t) = won_at {
+                        me.wins += 1;
+                        me.win_time += t;
+                    }
+                    Message::Result {
+                        stage,
+                        won: won_at.is_some(),
+                        time_ms: (won_at.unwrap_or(0.0) * 1000.0) as u32,
+                        lives,
+                    }
+                }
+                GameEvents::GameWon | GameEvents::GameLost => Message::Done,
+                _ => continue,
+            };
+            if let Some(client) = &mut self.client {
+                if client.send(&message).is_err() {
+                    self.client = None;
+                }
+            }
+        }
+    }
+
+    /// Takes in whatever the opponent has sent since last frame
+    pub fn receive(&mut self) {
+        let Some(client) = &mut self.client else {
+            return;
+        };
+        let messages = match client.poll() {
+            Ok(messages) => messages,
+            Err(_) => {
+                self.client = None;
+                return;
+            }
+        };
+        for message in messages {
+            match message {
+                Message::Result {
+                    stage,
+                    won,
+                    time_ms,
+                    lives,
+                } => {
+                    let them = self.players.player_mut(1);
+                    them.lives = lives;
+                    if won {
+                        them.wins += 1;
+                        them.win_time += time_ms as f32 / 1000.0;
+                    }
+                    self.opponent_stage = stage + 1;
+                }
+                Message::Done => self.opponent_done = true,
+                Message::Left => self.client = None,
+                _ => {}
+            }
+        }
+    }
+
+    /// Whether the opponent's run could still change the standings
+    pub fn waiting(&self) -> bool {
+        self.client.is_some() && !self.opponent_done
+    }
+
+    pub fn standings(&self) -> Vec<Standing> {
+        self.players.standings()
+    }
+
+    /// Opponent's progress, drawn under the HUD during our run
+    pub fn draw_opponent(&self, game_data: &GameState) {
+        let strings = &game_data.strings;
+        let them = self.players.player(1);
+        let status = if self.client.is_none() && !self.opponent_done {
+            strings.get("online.left").to_string()
+        } else {
+            format!(
+                "{}/{}  {} {}",
+                self.opponent_stage.min(self.run_length),
+                self.run_length,
+                strings.get("results.wins"),
+                them.wins
+            )
+        };
+        draw_text(
+            &format!("{} {status}", strings.get("online.opponent")),
+            630.0,
+            84.0,
+            28.0,
+            PLAYER_COLORS[1],
+        );
+    }
+}
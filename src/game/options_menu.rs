@@ -0,0 +1,157 @@
+use macroquad::prelude::*;
+
+use super::{
+    i18n::Strings,
+    ui::{draw_text_centered, Button},
+    ActiveState, FrameInput, GameEvents, GameState,
+};
+
+const SCREEN_EFFECTS_BUTTON: Button = Button::new(280.0, 130.0, 400.0, 48.0);
+const LANGUAGE_BUTTON: Button = Button::new(280.0, 190.0, 400.0, 48.0);
+const COLORBLIND_BUTTON: Button = Button::new(280.0, 250.0, 400.0, 48.0);
+const TIMER_BUTTON: Button = Button::new(280.0, 310.0, 400.0, 48.0);
+const DRAG_ASSIST_BUTTON: Button = Button::new(280.0, 370.0, 400.0, 48.0);
+const SAVE_REPLAYS_BUTTON: Button = Button::new(280.0, 430.0, 400.0, 48.0);
+const BACK_BUTTON: Button = Button::new(380.0, 520.0, 200.0, 48.0);
+
+pub struct OptionsData {
+    mouse_pos: Vec2,
+}
+
+impl Default for OptionsData {
+    fn default() -> Self {
+        OptionsData {
+            mouse_pos: Vec2::ZERO,
+        }
+    }
+}
+
+fn on_off(strings: &Strings, v: bool) -> &str {
+    if v {
+        strings.get("options.on")
+    } else {
+        strings.get("options.off")
+    }
+}
+
+pub fn update(
+    game_data: &mut GameState,
+    input: FrameInput,
+    delta: f32,
+    events: &mut Vec<GameEvents>,
+) -> Result<(), ()> {
+    if let ActiveState::Options(o_data) = &mut game_data.active_state {
+        o_data.mouse_pos = input.mouse_position;
+
+        if SCREEN_EFFECTS_BUTTON.clicked(&input) {
+            game_data.settings.screen_effects = !game_data.settings.screen_effects;
+            game_data.camera_fx.enabled = game_data.settings.screen_effects;
+            game_data.settings.save();
+        }
+
+        if LANGUAGE_BUTTON.clicked(&input) {
+            game_data.settings.language = game_data.settings.language.next();
+            game_data.strings = Strings::new(game_data.settings.language);
+            game_data.settings.save();
+        }
+
+        if SAVE_REPLAYS_BUTTON.clicked(&input) {
+            game_data.settings.save_replays = !game_data.settings.save_replays;
+            game_data.settings.save();
+        }
+
+        let access = &mut game_data.settings.access;
+        if COLORBLIND_BUTTON.clicked(&input) {
+            access.colorblind = !access.colorblind;
+            game_data.settings.save();
+        } else if TIMER_BUTTON.clicked(&input) {
+            access.timer_multiplier = access.next_timer_multiplier();
+            game_data.settings.save();
+        } else if DRAG_ASSIST_BUTTON.clicked(&input) {
+            access.drag_assist = !access.drag_assist;
+            game_data.settings.save();
+        }
+
+        if BACK_BUTTON.clicked(&input) {
+            events.push(GameEvents::MainMenuReturn);
+        }
+
+        Ok(())
+    } else {
+        Err(())
+    }
+}
+
+pub fn draw(game_data: &GameState) -> Result<(), ()> {
+    if let ActiveState::Options(o_data) = &game_data.active_state {
+        draw_texture(
+            game_data.textures.get("title_screen_bkgd").unwrap(),
+            0.0,
+            0.0,
+            WHITE,
+        );
+        draw_rectangle(0.0, 0.0, 960.0, 600.0, Color::new(0.0, 0.0, 0.0, 0.7));
+        let strings = &game_data.strings;
+        draw_text_centered(
+            strings.get("options.title"),
+            Vec2::new(480.0, 70.0),
+            64.0,
+            WHITE,
+        );
+
+        SCREEN_EFFECTS_BUTTON.draw(
+            &format!(
+                "{}: {}",
+                strings.get("options.screen_effects"),
+                on_off(strings, game_data.settings.screen_effects)
+            ),
+            &o_data.mouse_pos,
+        );
+        LANGUAGE_BUTTON.draw(
+            &format!(
+                "{}: {}",
+                strings.get("options.language"),
+                strings.get("language.name")
+            ),
+            &o_data.mouse_pos,
+        );
+        let access = &game_data.settings.access;
+        COLORBLIND_BUTTON.draw(
+            &format!(
+                "{}: {}",
+                strings.get("options.colorblind"),
+                on_off(strings, access.colorblind)
+            ),
+            &o_data.mouse_pos,
+        );
+        TIMER_BUTTON.draw(
+            &format!(
+                "{}: x{}",
+                strings.get("options.timer"),
+                access.timer_multiplier
+            ),
+            &o_data.mouse_pos,
+        );
+        DRAG_ASSIST_BUTTON.draw(
+            &format!(
+                "{}: {}",
+                strings.get("options.drag_assist"),
+                on_off(strings, access.drag_assist)
+            ),
+            &o_data.mouse_pos,
+        );
+        SAVE_REPLAYS_BUTTON.draw(
+            &format!(
+                "{}: {}",
+                strings.get("options.save_replays"),
+                on_off(strings, game_data.settings.save_replays)
+            ),
+            &o_data.mouse_pos,
+        );
+        BACK_BUTTON.draw(strings.get("menu.back"), &o_data.mouse_pos);
+
+        Ok(())
+    } else {
+        Err(())
+    }
+}
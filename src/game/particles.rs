@@ -0,0 +1,388 @@
+use std::{cell::OnceCell, collections::HashMap};
+
+use super::tween::Lerp;
+use macroquad::{
+    miniquad::{BlendFactor, BlendState, BlendValue, Equation, PipelineParams},
+    prelude::*,
+    rand::gen_range,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlendMode {
+    Alpha,
+    Additive,
+}
+
+/// Everything needed to spawn and simulate one kind of particle.
+/// Ranges are `(min, max)` and get rolled per particle.
+#[derive(Debug, Clone, Copy)]
+pub struct EmitterConfig {
+    pub texture: &'static str,
+    pub blend: BlendMode,
+    pub capacity: usize,
+    /// Particles per second while the emitter is active
+    pub spawn_rate: f32,
+    /// Particles spawned by a single call to `Emitter::burst`
+    pub burst: usize,
+    /// Half size of the box around the emitter position particles spawn in
+    pub spawn_area: Vec2,
+    /// Center of the launch cone in radians, 0 is to the right
+    pub direction: f32,
+    /// Half angle of the launch cone in radians
+    pub spread: f32,
+    pub speed: (f32, f32),
+    pub lifetime: (f32, f32),
+    pub rotation: (f32, f32),
+    pub ang_velocity: (f32, f32),
+    pub start_scale: f32,
+    pub end_scale: f32,
+    pub start_color: Color,
+    pub end_color: Color,
+    pub gravity: Vec2,
+    /// Fraction of velocity lost per second
+    pub drag: f32,
+}
+
+impl Default for EmitterConfig {
+    fn default() -> Self {
+        EmitterConfig {
+            texture: "smoke_particle",
+            blend: BlendMode::Alpha,
+            capacity: 64,
+            spawn_rate: 0.0,
+            burst: 0,
+            spawn_area: Vec2::ZERO,
+            direction: 0.0,
+            spread: std::f32::consts::PI,
+            speed: (0.0, 0.0),
+            lifetime: (1.0, 1.0),
+            rotation: (0.0, 0.0),
+            ang_velocity: (0.0, 0.0),
+            start_scale: 1.0,
+            end_scale: 1.0,
+            start_color: WHITE,
+            end_color: Color::new(1.0, 1.0, 1.0, 0.0),
+            gravity: Vec2::ZERO,
+            drag: 0.0,
+        }
+    }
+}
+
+/// Water spraying out of a leaking pipe
+pub fn pipes_leak() -> EmitterConfig {
+    EmitterConfig {
+        capacity: 32,
+        spawn_rate: 13.0,
+        direction: std::f32::consts::FRAC_PI_2,
+        spread: 0.2,
+        speed: (40.0, 60.0),
+        lifetime: (1.0, 1.0),
+        ang_velocity: (1.0, 1.0),
+        start_scale: 0.8,
+        end_scale: 1.2,
+        start_color: Color::new(0.7, 0.8, 1.0, 1.0),
+        end_color: Color::new(0.7, 0.8, 1.0, 0.0),
+        gravity: Vec2::new(0.0, 40.0),
+        ..Default::default()
+    }
+}
+
+/// Dust cloud left behind when the laser hits the asteroid
+pub fn asteroid_explosion() -> EmitterConfig {
+    EmitterConfig {
+        blend: BlendMode::Additive,
+        capacity: 32,
+        burst: 32,
+        spawn_area: Vec2::new(50.0, 50.0),
+        speed: (50.0, 280.0),
+        lifetime: (0.4, 0.6),
+        rotation: (0.0, 6.2),
+        ang_velocity: (-1.0, 1.0),
+        start_scale: 0.6,
+        end_scale: 1.6,
+        start_color: Color::new(0.8, 0.4, 0.4, 0.5),
+        end_color: Color::new(0.6, 0.3, 0.3, 0.0),
+        drag: 2.0,
+        ..Default::default()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Particle {
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub rotation: f32,
+    pub ang_velocity: f32,
+    pub age: f32,
+    pub lifetime: f32,
+}
+
+impl Particle {
+    fn life_progress(&self) -> f32 {
+        clamp(self.age / self.lifetime, 0.0, 1.0)
+    }
+}
+
+/// Fixed capacity particle storage, spawns past capacity are dropped
+pub struct ParticlePool {
+    particles: Vec<Particle>,
+    /// Kept separately, `Vec::with_capacity` may hand out more room than asked for
+    capacity: usize,
+}
+
+impl ParticlePool {
+    pub fn new(capacity: usize) -> Self {
+        ParticlePool {
+            particles: Vec::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn spawn(&mut self, particle: Particle) -> bool {
+        if self.particles.len() >= self.capacity {
+            return false;
+        }
+        self.particles.push(particle);
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.particles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.particles.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Particle> {
+        self.particles.iter()
+    }
+
+    fn update(&mut self, delta: f32, gravity: Vec2, drag: f32) {
+        let drag_factor = clamp(1.0 - drag * delta, 0.0, 1.0);
+        let mut i = 0;
+        while i < self.particles.len() {
+            let p = &mut self.particles[i];
+            p.age += delta;
+            if p.age >= p.lifetime {
+                self.particles.swap_remove(i);
+                continue;
+            }
+            p.velocity = (p.velocity + gravity * delta) * drag_factor;
+            p.position += p.velocity * delta;
+            p.rotation += p.ang_velocity * delta;
+            i += 1;
+        }
+    }
+}
+
+pub struct Emitter {
+    pub position: Vec2,
+    pub config: EmitterConfig,
+    /// Continuous spawning only happens while active, bursts always go off
+    pub active: bool,
+    spawn_accum: f32,
+    pool: ParticlePool,
+}
+
+impl Emitter {
+    pub fn new(position: Vec2, config: EmitterConfig) -> Self {
+        Emitter {
+            position,
+            config,
+            active: true,
+            spawn_accum: 0.0,
+            pool: ParticlePool::new(config.capacity),
+        }
+    }
+
+    pub fn particles(&self) -> &ParticlePool {
+        &self.pool
+    }
+
+    pub fn burst(&mut self) {
+        for _ in 0..self.config.burst {
+            self.spawn_one();
+        }
+    }
+
+    fn spawn_one(&mut self) {
+        let c = &self.config;
+        let angle = c.direction + gen_range(-c.spread, c.spread);
+        let offset = Vec2::new(
+            gen_range(-c.spawn_area.x, c.spawn_area.x),
+            gen_range(-c.spawn_area.y, c.spawn_area.y),
+        );
+        self.pool.spawn(Particle {
+            position: self.position + offset,
+            velocity: Vec2::from_angle(angle) * gen_range(c.speed.0, c.speed.1),
+            rotation: gen_range(c.rotation.0, c.rotation.1),
+            ang_velocity: gen_range(c.ang_velocity.0, c.ang_velocity.1),
+            age: 0.0,
+            lifetime: gen_range(c.lifetime.0, c.lifetime.1),
+        });
+    }
+
+    pub fn update(&mut self, delta: f32) {
+        self.pool
+            .update(delta, self.config.gravity, self.config.drag);
+
+        if self.active && self.config.spawn_rate > 0.0 {
+            self.spawn_accum += self.config.spawn_rate * delta;
+            while self.spawn_accum >= 1.0 {
+                self.spawn_accum -= 1.0;
+                self.spawn_one();
+            }
+        }
+    }
+
+    pub fn draw(&self, textures: &HashMap<&str, Texture2D>) {
+        let tex = textures.get(self.config.texture).unwrap();
+        let size = Vec2::new(tex.width(), tex.height());
+
+        if self.config.blend == BlendMode::Additive {
+            gl_use_material(&additive_material());
+        }
+        for p in self.pool.iter() {
+            let t = p.life_progress();
+            let scale =
+                self.config.start_scale + (self.config.end_scale - self.config.start_scale) * t;
+            let color = self.config.start_color.lerp(self.config.end_color, t);
+            let dest = size * scale;
+            draw_texture_ex(
+                tex,
+                p.position.x - dest.x / 2.0,
+                p.position.y - dest.y / 2.0,
+                color,
+                DrawTextureParams {
+                    dest_size: Some(dest),
+                    rotation: p.rotation,
+                    ..Default::default()
+                },
+            );
+        }
+        if self.config.blend == BlendMode::Additive {
+            gl_use_default_material();
+        }
+    }
+}
+
+const PARTICLE_VERTEX: &str = r#"#version 100
+attribute vec3 position;
+attribute vec2 texcoord;
+attribute vec4 color0;
+
+varying lowp vec2 uv;
+varying lowp vec4 color;
+
+uniform mat4 Model;
+uniform mat4 Projection;
+
+void main() {
+    gl_Position = Projection * Model * vec4(position, 1);
+    color = color0 / 255.0;
+    uv = texcoord;
+}"#;
+
+const PARTICLE_FRAGMENT: &str = r#"#version 100
+varying lowp vec4 color;
+varying lowp vec2 uv;
+
+uniform sampler2D Texture;
+
+void main() {
+    gl_FragColor = color * texture2D(Texture, uv);
+}"#;
+
+thread_local! {
+    static ADDITIVE_MATERIAL: OnceCell<Material> = const { OnceCell::new() };
+}
+
+// Built on first use since materials need the graphics context to exist
+fn additive_material() -> Material {
+    ADDITIVE_MATERIAL.with(|m| {
+        m.get_or_init(|| {
+            load_material(
+                ShaderSource::Glsl {
+                    vertex: PARTICLE_VERTEX,
+                    fragment: PARTICLE_FRAGMENT,
+                },
+                MaterialParams {
+                    pipeline_params: PipelineParams {
+                        color_blend: Some(BlendState::new(
+                            Equation::Add,
+                            BlendFactor::Value(BlendValue::SourceAlpha),
+                            BlendFactor::One,
+                        )),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+        })
+        .clone()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn particle(lifetime: f32) -> Particle {
+        Particle {
+            position: Vec2::ZERO,
+            velocity: Vec2::ZERO,
+            rotation: 0.0,
+            ang_velocity: 0.0,
+            age: 0.0,
+            lifetime,
+        }
+    }
+
+    #[test]
+    fn spawns_past_capacity_are_dropped() {
+        let mut pool = ParticlePool::new(3);
+        for _ in 0..3 {
+            assert!(pool.spawn(particle(1.0)));
+        }
+        assert!(!pool.spawn(particle(1.0)));
+        assert_eq!(pool.len(), 3);
+    }
+
+    #[test]
+    fn expired_particles_are_swapped_out() {
+        let mut pool = ParticlePool::new(4);
+        for lifetime in [0.5, 2.0, 0.5, 3.0] {
+            pool.spawn(particle(lifetime));
+        }
+        pool.update(1.0, Vec2::ZERO, 0.0);
+        // The last live particle fills each gap, so the order isn't kept
+        let left: Vec<f32> = pool.iter().map(|p| p.lifetime).collect();
+        assert_eq!(left, [3.0, 2.0]);
+        assert!(pool.iter().all(|p| p.age == 1.0));
+        pool.update(5.0, Vec2::ZERO, 0.0);
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn spawn_rate_carries_over_between_frames() {
+        let config = EmitterConfig {
+            spawn_rate: 2.0,
+            lifetime: (10.0, 10.0),
+            ..Default::default()
+        };
+        let mut emitter = Emitter::new(Vec2::ZERO, config);
+        emitter.update(0.25);
+        assert_eq!(emitter.particles().len(), 0);
+        emitter.update(0.25);
+        assert_eq!(emitter.particles().len(), 1);
+        for _ in 0..8 {
+            emitter.update(0.25);
+        }
+        assert_eq!(emitter.particles().len(), 5);
+        emitter.active = false;
+        emitter.update(1.0);
+        assert_eq!(emitter.particles().len(), 5);
+    }
+}
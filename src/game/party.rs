@@ -0,0 +1,301 @@
+use macroquad::prelude::*;
+
+/// Tag colors for players 1 to 4
+pub const PLAYER_COLORS: [Color; 4] = [
+    Color::new(0.95, 0.3, 0.3, 1.0),
+    Color::new(0.3, 0.6, 1.0, 1.0),
+    Color::new(0.35, 0.85, 0.35, 1.0),
+    Color::new(1.0, 0.8, 0.2, 1.0),
+];
+
+/// How many people share a run and how they take turns
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlayStyle {
+    Solo,
+    /// Players take turns at one shared run, each with their own lives and score
+    Party(u8),
+    /// Every player plays each stage in turn, then results are compared
+    Versus(u8),
+    /// Two players race through the same microgame at once on a split screen
+    Duel,
+}
+
+impl PlayStyle {
+    pub const ALL: [PlayStyle; 8] = [
+        PlayStyle::Solo,
+        PlayStyle::Party(2),
+        PlayStyle::Party(3),
+        PlayStyle::Party(4),
+        PlayStyle::Versus(2),
+        PlayStyle::Versus(3),
+        PlayStyle::Versus(4),
+        PlayStyle::Duel,
+    ];
+
+    pub fn code(self) -> String {
+        match self {
+            PlayStyle::Solo => "solo".to_string(),
+            PlayStyle::Party(n) => format!("party_{n}"),
+            PlayStyle::Versus(n) => format!("versus_{n}"),
+            PlayStyle::Duel => "duel".to_string(),
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<PlayStyle> {
+        PlayStyle::ALL.into_iter().find(|s| s.code() == code)
+    }
+
+    pub fn next(self) -> PlayStyle {
+        let i = PlayStyle::ALL.iter().position(|s| *s == self).unwrap();
+        PlayStyle::ALL[(i + 1) % PlayStyle::ALL.len()]
+    }
+
+    pub fn players(self) -> u8 {
+        match self {
+            PlayStyle::Solo => 1,
+            PlayStyle::Party(n) | PlayStyle::Versus(n) => n,
+            PlayStyle::Duel => 2,
+        }
+    }
+
+    /// String table key for the style's name, shown with the player count
+    pub fn name_key(self) -> &'static str {
+        match self {
+            PlayStyle::Solo => "menu.solo",
+            PlayStyle::Party(_) => "menu.party",
+            PlayStyle::Versus(_) => "menu.versus",
+            PlayStyle::Duel => "menu.duel",
+        }
+    }
+}
+
+/// One player's side of a run
+#[derive(Debug, Clone, PartialEq)]
+pub struct Player {
+    pub lives: u8,
+    pub score: u32,
+    pub wins: u8,
+    /// Seconds spent on the microgames they won, lower is better in versus
+    pub win_time: f32,
+    /// Microgames won in a row since their last loss
+    pub streak: u32,
+    pub lives_lost: u8,
+}
+
+impl Player {
+    fn new(lives: u8) -> Self {
+        Player {
+            lives,
+            score: 0,
+            wins: 0,
+            win_time: 0.0,
+            streak: 0,
+            lives_lost: 0,
+        }
+    }
+
+    pub fn is_out(&self) -> bool {
+        self.lives == 0
+    }
+}
+
+/// A player's place on the results screen
+#[derive(Debug, Clone, PartialEq)]
+pub struct Standing {
+    /// 1 for first, tied players share a rank
+    pub rank: u8,
+    /// Counting from 1
+    pub number: u8,
+    pub player: Player,
+}
+
+/// Everyone in a run and whose turn it is
+pub struct Players {
+    style: PlayStyle,
+    list: Vec<Player>,
+    current: usize,
+}
+
+impl Players {
+    pub fn new(style: PlayStyle, lives: u8) -> Self {
+        Players {
+            style,
+            list: (0..style.players()).map(|_| Player::new(lives)).collect(),
+            current: 0,
+        }
+    }
+
+    pub fn style(&self) -> PlayStyle {
+        self.style
+    }
+
+    pub fn is_multiplayer(&self) -> bool {
+        self.style != PlayStyle::Solo
+    }
+
+    pub fn current(&self) -> &Player {
+        &self.list[self.current]
+    }
+
+    pub fn current_mut(&mut self) -> &mut Player {
+        &mut self.list[self.current]
+    }
+
+    /// `index` counts from 0
+    pub fn player(&self, index: usize) -> &Player {
+        &self.list[index]
+    }
+
+    pub fn player_mut(&mut self, index: usize) -> &mut Player {
+        &mut self.list[index]
+    }
+
+    /// Counting from 1
+    pub fn current_number(&self) -> u8 {
+        self.current as u8 + 1
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Player> {
+        self.list.iter_mut()
+    }
+
+    pub fn all_out(&self) -> bool {
+        self.list.iter().all(Player::is_out)
+    }
+
+    /// Whether nobody else still has to play the current stage
+    pub fn last_in_stage(&self) -> bool {
+        match self.style {
+            PlayStyle::Versus(_) => self.list[self.current + 1..].iter().all(Player::is_out),
+            PlayStyle::Solo | PlayStyle::Party(_) | PlayStyle::Duel => true,
+        }
+    }
+
+    /// Hands over to whoever plays next, returning whether that starts a new stage
+    pub fn advance(&mut self) -> bool {
+        let new_stage = self.last_in_stage();
+        let len = self.list.len();
+        let from = if new_stage && matches!(self.style, PlayStyle::Versus(_)) {
+            // Back round to the first player still in
+            len - 1
+        } else {
+            self.current
+        };
+        if let Some(next) = (1..=len)
+            .map(|i| (from + i) % len)
+            .find(|i| !self.list[*i].is_out())
+        {
+            self.current = next;
+        }
+        new_stage
+    }
+
+    /// Players best first, by score in party and by wins then speed in versus and duels
+    pub fn standings(&self) -> Vec<Standing> {
+        let key = |p: &Player| match self.style {
+            PlayStyle::Versus(_) | PlayStyle::Duel => (p.wins as u32, -p.win_time, p.lives),
+            PlayStyle::Solo | PlayStyle::Party(_) => (p.score, 0.0, p.lives),
+        };
+        let mut order: Vec<usize> = (0..self.list.len()).collect();
+        order.sort_by(|a, b| {
+            key(&self.list[*b])
+                .partial_cmp(&key(&self.list[*a]))
+                .unwrap()
+        });
+
+        let mut standings: Vec<Standing> = vec![];
+        for (place, i) in order.into_iter().enumerate() {
+            let player = &self.list[i];
+            let rank = match standings.last() {
+                Some(prev) if key(&prev.player) == key(player) => prev.rank,
+                _ => place as u8 + 1,
+            };
+            standings.push(Standing {
+                rank,
+                number: i as u8 + 1,
+                player: player.clone(),
+            });
+        }
+        standings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order_of_play(players: &mut Players, turns: usize) -> Vec<(u8, bool)> {
+        (0..turns)
+            .map(|_| {
+                let new_stage = players.advance();
+                (players.current_number(), new_stage)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn party_rotates_past_players_who_are_out() {
+        let mut players = Players::new(PlayStyle::Party(3), 2);
+        assert_eq!(
+            order_of_play(&mut players, 3),
+            [(2, true), (3, true), (1, true)]
+        );
+        players.list[1].lives = 0;
+        assert_eq!(
+            order_of_play(&mut players, 3),
+            [(3, true), (1, true), (3, true)]
+        );
+    }
+
+    #[test]
+    fn versus_plays_each_stage_once_per_player() {
+        let mut players = Players::new(PlayStyle::Versus(3), 2);
+        assert_eq!(
+            order_of_play(&mut players, 6),
+            [
+                (2, false),
+                (3, false),
+                (1, true),
+                (2, false),
+                (3, false),
+                (1, true)
+            ]
+        );
+
+        // The last player being out ends the stage early
+        players.list[2].lives = 0;
+        players.current = 1;
+        assert!(players.last_in_stage());
+        assert_eq!(order_of_play(&mut players, 2), [(1, true), (2, false)]);
+    }
+
+    #[test]
+    fn standings_rank_and_share_ties() {
+        let mut players = Players::new(PlayStyle::Party(3), 3);
+        players.list[0].score = 200;
+        players.list[1].score = 500;
+        players.list[2].score = 200;
+        let ranks: Vec<(u8, u8)> = players
+            .standings()
+            .iter()
+            .map(|s| (s.rank, s.number))
+            .collect();
+        assert_eq!(ranks, [(1, 2), (2, 1), (2, 3)]);
+
+        // Versus goes by wins first, then whoever won faster
+        let mut players = Players::new(PlayStyle::Versus(2), 3);
+        players.list[0].wins = 4;
+        players.list[0].win_time = 9.0;
+        players.list[1].wins = 4;
+        players.list[1].win_time = 7.5;
+        assert_eq!(players.standings()[0].number, 2);
+    }
+
+    #[test]
+    fn style_codes_round_trip() {
+        for style in PlayStyle::ALL {
+            assert_eq!(PlayStyle::from_code(&style.code()), Some(style));
+        }
+        assert_eq!(PlayStyle::Duel.next(), PlayStyle::Solo);
+    }
+}
@@ -0,0 +1,181 @@
+use super::{rng::Rng, run_config::RunConfig};
+
+/// Picks a weighted microgame for `stage`, keeping the last `min_repeat_gap` picks out
+/// when there's anything else to choose from
+fn pick(config: &RunConfig, stage: u8, recent: &[u8], rng: &Rng) -> u8 {
+    let open: Vec<_> = config
+        .pool
+        .iter()
+        .filter(|e| e.unlock.is_open(stage) && e.weight > 0.0)
+        .collect();
+    let gap = (config.min_repeat_gap as usize).min(recent.len());
+    let fresh: Vec<_> = open
+        .iter()
+        .filter(|e| !recent[recent.len() - gap..].contains(&e.id))
+        .copied()
+        .collect();
+    // A gap the pool can't satisfy still shouldn't give back to back repeats
+    let not_last: Vec<_> = open
+        .iter()
+        .filter(|e| recent.last() != Some(&e.id))
+        .copied()
+        .collect();
+    let candidates = [fresh, not_last, open]
+        .into_iter()
+        .find(|c| !c.is_empty())
+        .expect("run config has no unlocked microgames");
+
+    let total: f32 = candidates.iter().map(|e| e.weight).sum();
+    let mut roll = rng.gen_range(0.0, total);
+    for entry in &candidates {
+        if roll < entry.weight {
+            return entry.id;
+        }
+        roll -= entry.weight;
+    }
+    candidates.last().unwrap().id
+}
+
+/// Queue of microgame ids for a whole run, popped from the end
+pub fn gen_microgame_queue(config: &RunConfig, rng: &Rng) -> Vec<u8> {
+    let mut order: Vec<u8> = Vec::with_capacity(config.length as usize);
+    let mut bosses = config.bosses.iter().cycle();
+    for stage in 0..config.length {
+        let boss = config
+            .boss_schedule
+            .is_boss_stage(stage, config.length)
+            .then(|| bosses.next())
+            .flatten();
+        let id = match boss {
+            Some(id) => *id,
+            None => pick(config, stage, &order, rng),
+        };
+        order.push(id);
+    }
+    order.reverse();
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::run_config::{BossSchedule, GameMode, PoolEntry, Unlock};
+
+    const SEEDS: u64 = 500;
+
+    fn play_order(config: &RunConfig, seed: u64) -> Vec<u8> {
+        let mut queue = gen_microgame_queue(config, &Rng::new(seed));
+        queue.reverse();
+        queue
+    }
+
+    fn entry(id: u8, weight: f32, unlock: Unlock) -> PoolEntry {
+        PoolEntry { id, weight, unlock }
+    }
+
+    #[test]
+    fn queue_covers_the_whole_run() {
+        for mode in GameMode::ALL {
+            let config = mode.config();
+            for seed in 0..SEEDS {
+                let queue = gen_microgame_queue(&config, &Rng::new(seed));
+                assert_eq!(queue.len(), config.length as usize);
+                assert!(queue.iter().all(
+                    |id| config.pool.iter().any(|e| e.id == *id) || config.bosses.contains(id)
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn repeats_keep_their_distance() {
+        for mode in GameMode::ALL {
+            let config = mode.config();
+            let gap = config.min_repeat_gap as usize;
+            for seed in 0..SEEDS {
+                let order = play_order(&config, seed);
+                for window in order.windows(gap + 1) {
+                    let first = window[0];
+                    assert!(
+                        !window[1..].contains(&first),
+                        "{} seed {seed}: {order:?}",
+                        mode.code()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn tight_pool_still_avoids_back_to_back() {
+        let config = RunConfig {
+            pool: vec![entry(0, 1.0, Unlock::Always), entry(1, 1.0, Unlock::Always)],
+            min_repeat_gap: 5,
+            ..GameMode::Marathon.config()
+        };
+        for seed in 0..SEEDS {
+            let order = play_order(&config, seed);
+            assert!(order.windows(2).all(|w| w[0] != w[1]));
+        }
+    }
+
+    #[test]
+    fn locked_microgames_wait_for_their_stage() {
+        let mut config = GameMode::Standard.config();
+        config.pool[3].unlock = Unlock::FromStage(6);
+        let locked = config.pool[3].id;
+        for seed in 0..SEEDS {
+            let order = play_order(&config, seed);
+            assert!(!order[..6].contains(&locked), "seed {seed}: {order:?}");
+        }
+    }
+
+    #[test]
+    fn weights_skew_the_picks() {
+        let config = RunConfig {
+            pool: vec![
+                entry(0, 8.0, Unlock::Always),
+                entry(1, 1.0, Unlock::Always),
+                entry(2, 1.0, Unlock::Always),
+                entry(3, 0.0, Unlock::Always),
+            ],
+            min_repeat_gap: 0,
+            boss_schedule: BossSchedule::Never,
+            ..GameMode::Marathon.config()
+        };
+        let mut counts = [0; 4];
+        for seed in 0..SEEDS {
+            for id in play_order(&config, seed) {
+                counts[id as usize] += 1;
+            }
+        }
+        assert_eq!(counts[3], 0);
+        assert!(counts[0] > counts[1] * 5 && counts[0] > counts[2] * 5);
+    }
+
+    #[test]
+    fn bosses_land_on_schedule() {
+        let config = RunConfig {
+            bosses: vec![100, 101],
+            boss_schedule: BossSchedule::Every(5),
+            ..GameMode::Standard.config()
+        };
+        for seed in 0..SEEDS {
+            let order = play_order(&config, seed);
+            assert_eq!(order[4], 100);
+            assert_eq!(order[9], 101);
+            assert_eq!(order[14], 100);
+            let boss_count = order.iter().filter(|id| **id >= 100).count();
+            assert_eq!(boss_count, 3);
+        }
+
+        let config = RunConfig {
+            bosses: vec![100],
+            boss_schedule: BossSchedule::MidpointAndEnd,
+            ..GameMode::Standard.config()
+        };
+        let order = play_order(&config, 1);
+        assert_eq!(order[7], 100);
+        assert_eq!(order[14], 100);
+    }
+}
@@ -0,0 +1,142 @@
+use macroquad::prelude::*;
+
+use super::{
+    party::{PlayStyle, Standing, PLAYER_COLORS},
+    tween::{Ease, Tween},
+    ui::draw_text_centered,
+    ActiveState, FrameInput, GameEvents, GameState, TransState,
+};
+
+const ROW_HEIGHT: f32 = 80.0;
+/// Gap between rows sliding in, last place first
+const ROW_STAGGER: f32 = 0.25;
+
+/// Ranking shown after a party, versus or online run
+pub struct ResultsData {
+    style: PlayStyle,
+    standings: Vec<Standing>,
+    /// Player 1 is us and player 2 the opponent
+    online: bool,
+    /// Online opponent is still playing, their standing can change
+    waiting: bool,
+    trans_state: TransState,
+    time: f32,
+}
+
+impl ResultsData {
+    pub fn new(style: PlayStyle, standings: Vec<Standing>) -> Self {
+        ResultsData {
+            style,
+            standings,
+            online: false,
+            waiting: false,
+            trans_state: TransState::TransIn(0.5),
+            time: 0.0,
+        }
+    }
+
+    pub fn online(standings: Vec<Standing>, waiting: bool) -> Self {
+        ResultsData {
+            online: true,
+            waiting,
+            ..ResultsData::new(PlayStyle::Versus(2), standings)
+        }
+    }
+
+    /// Takes in the opponent's latest results
+    pub fn refresh(&mut self, standings: Vec<Standing>, waiting: bool) {
+        self.standings = standings;
+        self.waiting = waiting;
+    }
+}
+
+pub fn update(
+    game_data: &mut GameState,
+    input: FrameInput,
+    delta: f32,
+    events: &mut Vec<GameEvents>,
+) -> Result<(), ()> {
+    if let ActiveState::Results(r_data) = &mut game_data.active_state {
+        r_data.time += delta;
+        r_data.trans_state = r_data.trans_state.update(delta, events, &input);
+        Ok(())
+    } else {
+        Err(())
+    }
+}
+
+pub fn draw(game_data: &GameState) -> Result<(), ()> {
+    if let ActiveState::Results(r_data) = &game_data.active_state {
+        let strings = &game_data.strings;
+        clear_background(Color::new(0.08, 0.08, 0.12, 1.0));
+        draw_text_centered(
+            strings.get("results.title"),
+            Vec2::new(480.0, 80.0),
+            72.0,
+            WHITE,
+        );
+
+        let count = r_data.standings.len();
+        for (i, standing) in r_data.standings.iter().enumerate() {
+            // Worst first, so the winner lands last
+            let delay = 0.5 + ROW_STAGGER * (count - 1 - i) as f32;
+            let x = Tween::new(-900.0, 0.0, 0.4, Ease::OutBack)
+                .with_delay(delay)
+                .sample(r_data.time);
+            let y = 150.0 + ROW_HEIGHT * i as f32;
+            let color = PLAYER_COLORS[standing.number as usize - 1];
+            let player = &standing.player;
+
+            draw_rectangle(
+                x + 120.0,
+                y,
+                720.0,
+                ROW_HEIGHT - 12.0,
+                Color::new(0.0, 0.0, 0.0, 0.5),
+            );
+            draw_rectangle(x + 120.0, y, 12.0, ROW_HEIGHT - 12.0, color);
+            draw_text(
+                &format!("#{}", standing.rank),
+                x + 150.0,
+                y + 48.0,
+                48.0,
+                WHITE,
+            );
+            let name = match (r_data.online, standing.number) {
+                (true, 1) => strings.get("online.you").to_string(),
+                (true, _) => strings.get("online.opponent").to_string(),
+                (false, number) => format!("{} {number}", strings.get("hud.player")),
+            };
+            draw_text(&name, x + 240.0, y + 48.0, 40.0, color);
+            let stats = match r_data.style {
+                PlayStyle::Versus(_) | PlayStyle::Duel => format!(
+                    "{} {}  {:.1}s",
+                    strings.get("results.wins"),
+                    player.wins,
+                    player.win_time
+                ),
+                PlayStyle::Solo | PlayStyle::Party(_) => {
+                    format!("{:06}", player.score)
+                }
+            };
+            draw_text(&stats, x + 520.0, y + 48.0, 36.0, YELLOW);
+            if player.is_out() {
+                draw_text(strings.get("results.out"), x + 760.0, y + 48.0, 28.0, GRAY);
+            }
+        }
+
+        if r_data.waiting {
+            draw_text_centered(
+                strings.get("online.still_playing"),
+                Vec2::new(480.0, 540.0),
+                32.0,
+                GRAY,
+            );
+        }
+
+        r_data.trans_state.draw_doors(&game_data.textures);
+        Ok(())
+    } else {
+        Err(())
+    }
+}
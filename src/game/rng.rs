@@ -0,0 +1,58 @@
+use macroquad::rand::{ChooseRandom, RandGenerator, RandomRange};
+
+/// Seeded random source handed to anything that affects how a run plays out, so
+/// runs, tests and simulations don't depend on the global generator. Cosmetic
+/// randomness like particles still uses the global one.
+pub struct Rng {
+    seed: u64,
+    state: RandGenerator,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        let state = RandGenerator::new();
+        state.srand(seed);
+        Rng { seed, state }
+    }
+
+    /// Seed from the global generator, for when nobody asked for a specific one
+    pub fn from_global() -> Self {
+        let seed = ((macroquad::rand::rand() as u64) << 32) | macroquad::rand::rand() as u64;
+        Rng::new(seed)
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn gen_range<T: RandomRange>(&self, low: T, high: T) -> T {
+        self.state.gen_range(low, high)
+    }
+
+    pub fn shuffle<T>(&self, items: &mut [T]) {
+        items.shuffle_with_state(&self.state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_numbers() {
+        let a = Rng::new(42);
+        let b = Rng::new(42);
+        for _ in 0..100 {
+            assert_eq!(a.gen_range(0, 1000), b.gen_range(0, 1000));
+        }
+    }
+
+    #[test]
+    fn shuffle_keeps_the_items() {
+        let rng = Rng::new(7);
+        let mut items: Vec<u8> = (0..10).collect();
+        rng.shuffle(&mut items);
+        items.sort();
+        assert_eq!(items, (0..10).collect::<Vec<u8>>());
+    }
+}
@@ -0,0 +1,202 @@
+/// Microgame time used from `from_stage` completed microgames onwards
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeTier {
+    pub from_stage: u8,
+    pub time: f32,
+}
+
+const fn tier(from_stage: u8, time: f32) -> TimeTier {
+    TimeTier { from_stage, time }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Unlock {
+    Always,
+    /// Only shows up once this many microgames have been played
+    FromStage(u8),
+}
+
+impl Unlock {
+    pub fn is_open(self, stage: u8) -> bool {
+        match self {
+            Unlock::Always => true,
+            Unlock::FromStage(from) => stage >= from,
+        }
+    }
+}
+
+/// A microgame the queue can draw from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PoolEntry {
+    pub id: u8,
+    /// Relative chance of being picked, 0.0 never is
+    pub weight: f32,
+    pub unlock: Unlock,
+}
+
+/// Stages the boss microgames go in, counting from 1
+#[derive(Debug, Clone, PartialEq)]
+pub enum BossSchedule {
+    Never,
+    Every(u8),
+    MidpointAndEnd,
+}
+
+impl BossSchedule {
+    /// Whether the zero based `stage` is a boss stage in a run of `length`
+    pub fn is_boss_stage(&self, stage: u8, length: u8) -> bool {
+        let number = stage + 1;
+        match self {
+            BossSchedule::Never => false,
+            BossSchedule::Every(every) => *every > 0 && number.is_multiple_of(*every),
+            BossSchedule::MidpointAndEnd => number == length.div_ceil(2) || number == length,
+        }
+    }
+}
+
+/// Everything that shapes a run, built from a `GameMode`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunConfig {
+    /// Microgames to clear to win
+    pub length: u8,
+    pub lives: u8,
+    /// Sorted by `from_stage`, the first one starting at 0
+    pub time_tiers: Vec<TimeTier>,
+    /// Microgames the queue is drawn from
+    pub pool: Vec<PoolEntry>,
+    /// Other microgames that have to come between two of the same one
+    pub min_repeat_gap: u8,
+    /// Boss microgame ids, used in turn on boss stages
+    pub bosses: Vec<u8>,
+    pub boss_schedule: BossSchedule,
+}
+
+impl RunConfig {
+    /// Time allowed for a microgame, shrinking as the run goes on
+    pub fn time_for(&self, microgames_completed: u8) -> f32 {
+        self.time_tiers
+            .iter()
+            .rev()
+            .find(|t| microgames_completed >= t.from_stage)
+            .map_or(self.time_tiers[0].time, |t| t.time)
+    }
+}
+
+const STANDARD_TIERS: [TimeTier; 3] = [tier(0, 5.0), tier(5, 4.0), tier(10, 3.0)];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameMode {
+    Short,
+    Standard,
+    Marathon,
+    OneLife,
+    /// Standard run seeded from the date, the same for everyone that day
+    Daily,
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 5] = [
+        GameMode::Short,
+        GameMode::Standard,
+        GameMode::Marathon,
+        GameMode::OneLife,
+        GameMode::Daily,
+    ];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            GameMode::Short => "short",
+            GameMode::Standard => "standard",
+            GameMode::Marathon => "marathon",
+            GameMode::OneLife => "one_life",
+            GameMode::Daily => "daily",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<GameMode> {
+        GameMode::ALL.into_iter().find(|m| m.code() == code)
+    }
+
+    pub fn next(self) -> GameMode {
+        let i = GameMode::ALL.iter().position(|m| *m == self).unwrap();
+        GameMode::ALL[(i + 1) % GameMode::ALL.len()]
+    }
+
+    /// String table key for the mode's name
+    pub fn name_key(self) -> &'static str {
+        match self {
+            GameMode::Short => "mode.short",
+            GameMode::Standard => "mode.standard",
+            GameMode::Marathon => "mode.marathon",
+            GameMode::OneLife => "mode.one_life",
+            GameMode::Daily => "mode.daily",
+        }
+    }
+
+    pub fn config(self) -> RunConfig {
+        let standard = RunConfig {
+            length: 15,
+            lives: 3,
+            time_tiers: STANDARD_TIERS.to_vec(),
+            pool: (0..8)
+                .map(|id| PoolEntry {
+                    id,
+                    weight: 1.0,
+                    unlock: Unlock::Always,
+                })
+                .collect(),
+            min_repeat_gap: 3,
+            bosses: vec![8, 9],
+            boss_schedule: BossSchedule::MidpointAndEnd,
+        };
+        match self {
+            GameMode::Short => RunConfig {
+                length: 8,
+                time_tiers: vec![tier(0, 5.0), tier(3, 4.0), tier(6, 3.0)],
+                ..standard
+            },
+            GameMode::Standard | GameMode::Daily => standard,
+            GameMode::Marathon => RunConfig {
+                length: 40,
+                time_tiers: vec![tier(0, 5.0), tier(10, 4.0), tier(20, 3.5), tier(30, 3.0)],
+                boss_schedule: BossSchedule::Every(10),
+                ..standard
+            },
+            GameMode::OneLife => RunConfig {
+                lives: 1,
+                ..standard
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn standard_tiers_match_the_original_timings() {
+        let config = GameMode::Standard.config();
+        assert_eq!(config.time_for(0), 5.0);
+        assert_eq!(config.time_for(4), 5.0);
+        assert_eq!(config.time_for(5), 4.0);
+        assert_eq!(config.time_for(9), 4.0);
+        assert_eq!(config.time_for(10), 3.0);
+        assert_eq!(config.time_for(200), 3.0);
+    }
+
+    #[test]
+    fn presets_are_sane() {
+        for mode in GameMode::ALL {
+            let config = mode.config();
+            assert!(config.length > 0 && config.lives > 0, "{}", mode.code());
+            assert!(!config.pool.is_empty());
+            assert_eq!(config.time_tiers[0].from_stage, 0);
+            assert!(config
+                .time_tiers
+                .windows(2)
+                .all(|w| w[0].from_stage < w[1].from_stage && w[0].time >= w[1].time));
+            assert_eq!(GameMode::from_code(mode.code()), Some(mode));
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use std::{collections::HashMap, env, fs, io, path::PathBuf};
+
+/// Folder all persistent game files live in, created on first write
+pub fn save_dir() -> PathBuf {
+    if let Ok(dir) = env::var("MICROSHIP_SAVE_DIR") {
+        return PathBuf::from(dir);
+    }
+    if let Ok(dir) = env::var("XDG_DATA_HOME") {
+        return PathBuf::from(dir).join("microship");
+    }
+    if let Ok(dir) = env::var("APPDATA") {
+        return PathBuf::from(dir).join("microship");
+    }
+    if let Ok(home) = env::var("HOME") {
+        return PathBuf::from(home).join(".local/share/microship");
+    }
+    PathBuf::from(".")
+}
+
+pub fn read_file(name: &str) -> Option<String> {
+    fs::read_to_string(save_dir().join(name)).ok()
+}
+
+pub fn write_file(name: &str, contents: &str) -> io::Result<()> {
+    let dir = save_dir();
+    fs::create_dir_all(&dir)?;
+    fs::write(dir.join(name), contents)
+}
+
+/// Parses `key = value` lines, skipping blanks and `#` comments
+pub fn parse_kv(text: &str) -> HashMap<String, String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .filter_map(|l| l.split_once('='))
+        .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+        .collect()
+}
+
+pub fn format_kv(entries: &[(&str, String)]) -> String {
+    entries
+        .iter()
+        .map(|(k, v)| format!("{k} = {v}\n"))
+        .collect()
+}
+
+pub fn read_kv(name: &str) -> HashMap<String, String> {
+    read_file(name).map(|t| parse_kv(&t)).unwrap_or_default()
+}
+
+pub fn write_kv(name: &str, entries: &[(&str, String)]) -> io::Result<()> {
+    write_file(name, &format_kv(entries))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kv_round_trips() {
+        let text = format_kv(&[
+            ("alpha", "1".to_string()),
+            ("beta", "two words".to_string()),
+        ]);
+        let kv = parse_kv(&text);
+        assert_eq!(kv.get("alpha").map(String::as_str), Some("1"));
+        assert_eq!(kv.get("beta").map(String::as_str), Some("two words"));
+    }
+
+    #[test]
+    fn kv_skips_comments_and_junk() {
+        let kv = parse_kv("# comment\n\nnot a pair\n key=  value \n");
+        assert_eq!(kv.len(), 1);
+        assert_eq!(kv.get("key").map(String::as_str), Some("value"));
+    }
+}
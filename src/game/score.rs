@@ -0,0 +1,261 @@
+use macroquad::prelude::*;
+
+use super::{
+    i18n::Strings,
+    run_config::GameMode,
+    save,
+    tween::{Ease, Lerp, Tween},
+};
+
+const SCORES_FILE: &str = "scores.txt";
+/// Runs kept for each mode
+const TABLE_SIZE: usize = 10;
+/// Rows of the table the run summary has room for
+const SHOWN: usize = 5;
+/// How long the score takes to count up on the run summary
+const COUNT_TIME: f32 = 1.0;
+
+const WIN_POINTS: u32 = 100;
+/// Extra for winning with the whole timer left, scaled down by how much of it was used.
+/// Going by share of the timer means slower accessibility timers don't pay out more
+const TIME_POINTS: f32 = 200.0;
+const BOSS_BONUS: u32 = 500;
+/// For clearing a whole run without losing a single life
+pub const NO_DAMAGE_BONUS: u32 = 1000;
+/// Every win in a row adds this much to the multiplier, up to `MAX_MULTIPLIER`
+const STREAK_STEP: f32 = 0.25;
+const MAX_MULTIPLIER: f32 = 3.0;
+
+/// What a win is multiplied by after `streak` wins in a row
+pub fn multiplier(streak: u32) -> f32 {
+    (1.0 + STREAK_STEP * streak as f32).min(MAX_MULTIPLIER)
+}
+
+/// Points for a microgame won `won_at` seconds into a `time_limit` timer
+pub fn win_points(time_limit: f32, won_at: f32, boss: bool, streak: u32) -> u32 {
+    let time_left = ((time_limit - won_at) / time_limit).clamp(0.0, 1.0);
+    let points = (WIN_POINTS as f32 + TIME_POINTS * time_left) * multiplier(streak);
+    points.round() as u32 + if boss { BOSS_BONUS } else { 0 }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HighScore {
+    pub mode: GameMode,
+    pub score: u32,
+    /// Whether the run was cleared rather than ending on its last life
+    pub won: bool,
+}
+
+/// Best scores of every mode, saved between sessions
+#[derive(Debug, Default)]
+pub struct HighScores {
+    /// Best first, older runs ahead of newer ones on a tie
+    entries: Vec<HighScore>,
+}
+
+impl HighScores {
+    pub fn load() -> Self {
+        save::read_file(SCORES_FILE)
+            .map(|t| HighScores::parse(&t))
+            .unwrap_or_default()
+    }
+
+    /// One `mode score won|lost` line per run, unreadable lines are dropped
+    fn parse(text: &str) -> Self {
+        let mut scores = HighScores::default();
+        for line in text.lines() {
+            let mut parts = line.split_whitespace();
+            let (Some(mode), Some(score), Some(outcome)) = (
+                parts.next().and_then(GameMode::from_code),
+                parts.next().and_then(|s| s.parse().ok()),
+                parts.next(),
+            ) else {
+                continue;
+            };
+            scores.add(HighScore {
+                mode,
+                score,
+                won: outcome == "won",
+            });
+        }
+        scores
+    }
+
+    fn encode(&self) -> String {
+        self.entries
+            .iter()
+            .map(|e| {
+                let outcome = if e.won { "won" } else { "lost" };
+                format!("{} {} {outcome}\n", e.mode.code(), e.score)
+            })
+            .collect()
+    }
+
+    pub fn top(&self, mode: GameMode) -> impl Iterator<Item = &HighScore> {
+        self.entries.iter().filter(move |e| e.mode == mode)
+    }
+
+    pub fn best(&self, mode: GameMode) -> Option<u32> {
+        self.top(mode).next().map(|e| e.score)
+    }
+
+    /// Puts the run in the table, giving back its place counting from 1 if it made it
+    pub fn add(&mut self, entry: HighScore) -> Option<usize> {
+        let mode = entry.mode;
+        let index = self
+            .entries
+            .iter()
+            .position(|e| e.score < entry.score)
+            .unwrap_or(self.entries.len());
+        let place = self.entries[..index]
+            .iter()
+            .filter(|e| e.mode == mode)
+            .count()
+            + 1;
+        self.entries.insert(index, entry);
+
+        // Knock whatever fell off the bottom out of this mode's table
+        let mut kept = 0;
+        self.entries.retain(|e| {
+            kept += (e.mode == mode) as usize;
+            e.mode != mode || kept <= TABLE_SIZE
+        });
+        (place <= TABLE_SIZE).then_some(place)
+    }
+
+    /// Adds the run and saves if it made the table
+    pub fn record(&mut self, entry: HighScore) -> Option<usize> {
+        let place = self.add(entry);
+        if place.is_some() {
+            let _ = save::write_file(SCORES_FILE, &self.encode());
+        }
+        place
+    }
+}
+
+/// What the run summary shows about a solo run's score
+pub struct ScoreSummary {
+    pub score: u32,
+    /// Best in the mode before this run, None if it's the first
+    pub previous_best: Option<u32>,
+    /// Where the run landed in its mode's table, counting from 1
+    pub place: Option<usize>,
+    table: Vec<HighScore>,
+}
+
+impl ScoreSummary {
+    /// Saves the run to the high scores if it's good enough
+    pub fn record(mode: GameMode, score: u32, won: bool) -> Self {
+        let mut scores = HighScores::load();
+        let previous_best = scores.best(mode);
+        let place = scores.record(HighScore { mode, score, won });
+        ScoreSummary {
+            score,
+            previous_best,
+            place,
+            table: scores.top(mode).take(SHOWN).cloned().collect(),
+        }
+    }
+
+    pub fn is_new_best(&self) -> bool {
+        self.previous_best.is_none_or(|best| self.score > best)
+    }
+
+    /// Score counting up from `t` = 0, then how it compares and the mode's table
+    pub fn draw(&self, strings: &Strings, x: f32, t: f32) {
+        let counted = Tween::new(0.0, self.score as f32, COUNT_TIME, Ease::OutQuad).sample(t);
+        draw_text(strings.get("score.score"), x, 130.0, 28.0, GRAY);
+        draw_text(&format!("{:06}", counted as u32), x, 180.0, 56.0, YELLOW);
+        if t < COUNT_TIME {
+            return;
+        }
+
+        if self.is_new_best() {
+            let flash = (t * 8.0).sin() * 0.5 + 0.5;
+            let color = YELLOW.lerp(WHITE, flash);
+            draw_text(strings.get("score.new_best"), x, 218.0, 28.0, color);
+        } else if let Some(best) = self.previous_best {
+            draw_text(
+                &format!(
+                    "{} {best:06}  -{}",
+                    strings.get("score.best"),
+                    best - self.score
+                ),
+                x,
+                218.0,
+                28.0,
+                LIGHTGRAY,
+            );
+        }
+
+        draw_text(strings.get("score.high_scores"), x, 262.0, 24.0, WHITE);
+        for (i, entry) in self.table.iter().enumerate() {
+            let y = 292.0 + 26.0 * i as f32;
+            let color = if self.place == Some(i + 1) {
+                YELLOW
+            } else {
+                LIGHTGRAY
+            };
+            draw_text(&format!("{}. {:06}", i + 1, entry.score), x, y, 26.0, color);
+            if entry.won {
+                draw_text(strings.get("score.cleared"), x + 160.0, y, 20.0, color);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(mode: GameMode, score: u32) -> HighScore {
+        HighScore {
+            mode,
+            score,
+            won: false,
+        }
+    }
+
+    #[test]
+    fn quick_wins_and_streaks_pay_more() {
+        assert_eq!(win_points(4.0, 4.0, false, 0), 100);
+        assert_eq!(win_points(4.0, 2.0, false, 0), 200);
+        assert_eq!(win_points(4.0, 2.0, false, 2), 300);
+        assert_eq!(win_points(4.0, 4.0, true, 0), 600);
+        assert_eq!(multiplier(100), MAX_MULTIPLIER);
+        // Slower timers only stretch the time, not the points
+        assert_eq!(
+            win_points(8.0, 4.0, false, 0),
+            win_points(4.0, 2.0, false, 0)
+        );
+    }
+
+    #[test]
+    fn tables_are_kept_per_mode() {
+        let mut scores = HighScores::default();
+        for score in 1..=TABLE_SIZE as u32 {
+            assert_eq!(scores.add(entry(GameMode::Standard, score * 100)), Some(1));
+        }
+        assert_eq!(scores.add(entry(GameMode::Marathon, 50)), Some(1));
+        assert_eq!(scores.add(entry(GameMode::Standard, 50)), None);
+        // Ties go behind the runs that got there first
+        assert_eq!(scores.add(entry(GameMode::Standard, 500)), Some(7));
+        assert_eq!(scores.top(GameMode::Standard).count(), TABLE_SIZE);
+        assert_eq!(scores.best(GameMode::Standard), Some(1000));
+        assert_eq!(scores.best(GameMode::Marathon), Some(50));
+        assert_eq!(scores.best(GameMode::Short), None);
+    }
+
+    #[test]
+    fn table_round_trips() {
+        let mut scores = HighScores::default();
+        scores.add(entry(GameMode::Daily, 1234));
+        scores.add(HighScore {
+            mode: GameMode::Standard,
+            score: 4321,
+            won: true,
+        });
+        let text = format!("{}junk line\nstandard lots won\n", scores.encode());
+        assert_eq!(HighScores::parse(&text).entries, scores.entries);
+    }
+}
@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+
+use super::{i18n::Language, party::PlayStyle, run_config::GameMode, save};
+
+const SETTINGS_FILE: &str = "settings.txt";
+
+/// Timer multipliers the options screen cycles through
+pub const TIMER_MULTIPLIERS: [f32; 4] = [1.0, 1.25, 1.5, 2.0];
+
+#[derive(Clone, Copy)]
+pub struct Accessibility {
+    /// Shape overlays on anything that is otherwise only told apart by red vs green
+    pub colorblind: bool,
+    /// Scales the time allowed for every microgame
+    pub timer_multiplier: f32,
+    /// Lets drag microgames progress by holding a mouse button or space
+    pub drag_assist: bool,
+}
+
+impl Default for Accessibility {
+    fn default() -> Self {
+        Accessibility {
+            colorblind: false,
+            timer_multiplier: 1.0,
+            drag_assist: false,
+        }
+    }
+}
+
+impl Accessibility {
+    pub fn next_timer_multiplier(&self) -> f32 {
+        let i = TIMER_MULTIPLIERS
+            .iter()
+            .position(|m| *m == self.timer_multiplier)
+            .unwrap_or(0);
+        TIMER_MULTIPLIERS[(i + 1) % TIMER_MULTIPLIERS.len()]
+    }
+}
+
+pub struct Settings {
+    /// Screen shake, hit-stop and zoom punches
+    pub screen_effects: bool,
+    pub language: Language,
+    pub access: Accessibility,
+    /// Mode picked on the main menu
+    pub mode: GameMode,
+    /// Solo, party or versus, also picked on the main menu
+    pub style: PlayStyle,
+    /// Keep every input of finished runs so the history screen can play them back
+    pub save_replays: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            screen_effects: true,
+            language: Language::English,
+            access: Accessibility::default(),
+            mode: GameMode::Standard,
+            style: PlayStyle::Solo,
+            save_replays: true,
+        }
+    }
+}
+
+fn read_bool(kv: &HashMap<String, String>, key: &str, default: bool) -> bool {
+    match kv.get(key).map(String::as_str) {
+        Some("true") => true,
+        Some("false") => false,
+        _ => default,
+    }
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        let kv = save::read_kv(SETTINGS_FILE);
+        let default = Settings::default();
+        Settings {
+            screen_effects: read_bool(&kv, "screen_effects", default.screen_effects),
+            language: kv
+                .get("language")
+                .and_then(|c| Language::from_code(c))
+                .unwrap_or(default.language),
+            access: Accessibility {
+                colorblind: read_bool(&kv, "colorblind", default.access.colorblind),
+                timer_multiplier: kv
+                    .get("timer_multiplier")
+                    .and_then(|m| m.parse().ok())
+                    .filter(|m| TIMER_MULTIPLIERS.contains(m))
+                    .unwrap_or(default.access.timer_multiplier),
+                drag_assist: read_bool(&kv, "drag_assist", default.access.drag_assist),
+            },
+            mode: kv
+                .get("mode")
+                .and_then(|c| GameMode::from_code(c))
+                .unwrap_or(default.mode),
+            style: kv
+                .get("style")
+                .and_then(|c| PlayStyle::from_code(c))
+                .unwrap_or(default.style),
+            save_replays: read_bool(&kv, "save_replays", default.save_replays),
+        }
+    }
+
+    pub fn save(&self) {
+        let _ = save::write_kv(
+            SETTINGS_FILE,
+            &[
+                ("screen_effects", self.screen_effects.to_string()),
+                ("language", self.language.code().to_string()),
+                ("colorblind", self.access.colorblind.to_string()),
+                ("timer_multiplier", self.access.timer_multiplier.to_string()),
+                ("drag_assist", self.access.drag_assist.to_string()),
+                ("mode", self.mode.code().to_string()),
+                ("style", self.style.code()),
+                ("save_replays", self.save_replays.to_string()),
+            ],
+        );
+    }
+}
@@ -0,0 +1,123 @@
+use super::{
+    bot::{Bot, BotSkill},
+    gameplay::InGameData,
+    rng::Rng,
+    run_config::GameMode,
+    settings::Accessibility,
+    GameEvents,
+};
+
+/// How one microgame of a simulated run went
+#[derive(Debug, Clone, PartialEq)]
+pub struct MicrogameResult {
+    pub name: &'static str,
+    pub boss: bool,
+    /// Zero based position in the run
+    pub stage: u8,
+    pub time_limit: f32,
+    /// Seconds the bot took to win, None if it lost
+    pub won_at: Option<f32>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunReport {
+    pub seed: u64,
+    pub won: bool,
+    pub results: Vec<MicrogameResult>,
+}
+
+impl RunReport {
+    /// Number of microgames finished before the run ended
+    pub fn stages_survived(&self) -> usize {
+        self.results.len()
+    }
+}
+
+/// Plays a whole run with the bot and no window, audio or menus involved
+pub fn simulate_run(mode: GameMode, seed: u64, skill: BotSkill, delta: f32) -> RunReport {
+    let mut run = InGameData::new(mode.config(), Rng::new(seed));
+    let mut bot = Bot::new(skill, seed);
+    let access = Accessibility::default();
+    let mut events: Vec<GameEvents> = vec![];
+    let mut results = vec![];
+
+    loop {
+        let input = bot.input(run.bot_action(), delta);
+        run.step(input, delta, &access, &mut events);
+        for event in events.drain(..) {
+            match event {
+                GameEvents::MicrogameEnded {
+                    name,
+                    boss,
+                    stage,
+                    time_limit,
+                    won_at,
+                } => results.push(MicrogameResult {
+                    name,
+                    boss,
+                    stage,
+                    time_limit,
+                    won_at,
+                }),
+                GameEvents::GameWon => {
+                    return RunReport {
+                        seed,
+                        won: true,
+                        results,
+                    }
+                }
+                GameEvents::GameLost => {
+                    return RunReport {
+                        seed,
+                        won: false,
+                        results,
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: f32 = 1.0 / 60.0;
+
+    #[test]
+    fn perfect_bot_finishes_every_run() {
+        for mode in GameMode::ALL {
+            for seed in 0..5 {
+                let report = simulate_run(mode, seed, BotSkill::PERFECT, FRAME);
+                assert!(report.won, "{} run lost with seed {seed}", mode.code());
+                assert_eq!(report.stages_survived(), mode.config().length as usize);
+                assert!(report.results.iter().all(|r| r.won_at.is_some()));
+            }
+        }
+    }
+
+    #[test]
+    fn timer_tiers_shrink_over_the_run() {
+        let report = simulate_run(GameMode::Standard, 3, BotSkill::PERFECT, FRAME);
+        let limits: Vec<f32> = report.results.iter().map(|r| r.time_limit).collect();
+        assert_eq!(limits[0], 5.0);
+        assert_eq!(limits[5], 4.0);
+        assert_eq!(limits[13], 3.0);
+    }
+
+    #[test]
+    fn bosses_come_at_the_midpoint_and_end() {
+        for seed in 0..5 {
+            let report = simulate_run(GameMode::Standard, seed, BotSkill::PERFECT, FRAME);
+            let bosses: Vec<u8> = report
+                .results
+                .iter()
+                .filter(|r| r.boss)
+                .map(|r| r.stage)
+                .collect();
+            assert_eq!(bosses, [7, 14]);
+            assert!(report.results[7].time_limit > report.results[6].time_limit);
+        }
+    }
+}
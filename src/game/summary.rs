@@ -0,0 +1,227 @@
+use macroquad::prelude::*;
+
+use super::{
+    daily::DailySummary,
+    doors_opening, draw_doors,
+    microgames::plate_key_of,
+    run_config::GameMode,
+    score::ScoreSummary,
+    sim::MicrogameResult,
+    tween::{Ease, Tween},
+    ui::{draw_text_centered, Button},
+    ActiveState, FrameInput, GameEvents, GameState,
+};
+
+const DOORS_TIME: f32 = 0.5;
+/// Gap between microgames popping in, in the order they were played
+const CELL_STAGGER: f32 = 0.08;
+const CELL_POP_TIME: f32 = 0.3;
+/// Where the microgames are laid out, left of the score column
+const GRID: Rect = Rect {
+    x: 30.0,
+    y: 90.0,
+    w: 570.0,
+    h: 340.0,
+};
+/// Room under each plate for how it went
+const LABEL_HEIGHT: f32 = 24.0;
+const SCORE_X: f32 = 640.0;
+const RETRY_BUTTON: Button = Button::new(150.0, 536.0, 200.0, 48.0);
+const NEW_RUN_BUTTON: Button = Button::new(380.0, 536.0, 200.0, 48.0);
+const MENU_BUTTON: Button = Button::new(610.0, 536.0, 200.0, 48.0);
+
+/// End of a solo run, every microgame played and how the score stacks up
+pub struct SummaryData {
+    won: bool,
+    mode: GameMode,
+    seed: u64,
+    history: Vec<MicrogameResult>,
+    score: ScoreSummary,
+    daily: Option<DailySummary>,
+    time: f32,
+    mouse_pos: Vec2,
+}
+
+impl SummaryData {
+    pub fn new(
+        won: bool,
+        mode: GameMode,
+        seed: u64,
+        history: Vec<MicrogameResult>,
+        score: ScoreSummary,
+        daily: Option<DailySummary>,
+    ) -> Self {
+        SummaryData {
+            won,
+            mode,
+            seed,
+            history,
+            score,
+            daily,
+            time: 0.0,
+            mouse_pos: Vec2::ZERO,
+        }
+    }
+
+    /// When the score starts counting, once every microgame is in
+    fn score_start(&self) -> f32 {
+        DOORS_TIME + CELL_STAGGER * self.history.len() as f32 + CELL_POP_TIME
+    }
+}
+
+/// Columns and cell size that fit `count` microgames in the grid, growing smaller for long runs
+fn grid_layout(count: usize) -> (usize, Vec2) {
+    let mut columns = 5;
+    loop {
+        let width = GRID.w / columns as f32;
+        // Plates are 4:3, with room for the time underneath
+        let height = width * 0.75 + LABEL_HEIGHT;
+        if count.div_ceil(columns) as f32 * height <= GRID.h {
+            return (columns, Vec2::new(width, height));
+        }
+        columns += 1;
+    }
+}
+
+pub fn update(
+    game_data: &mut GameState,
+    input: FrameInput,
+    delta: f32,
+    events: &mut Vec<GameEvents>,
+) -> Result<(), ()> {
+    if let ActiveState::Summary(s_data) = &mut game_data.active_state {
+        s_data.time += delta;
+        s_data.mouse_pos = input.mouse_position;
+        if RETRY_BUTTON.clicked(&input) {
+            events.push(GameEvents::RetryRun {
+                mode: s_data.mode,
+                seed: s_data.seed,
+            });
+        } else if NEW_RUN_BUTTON.clicked(&input) {
+            events.push(GameEvents::StartGameplay);
+        } else if MENU_BUTTON.clicked(&input) {
+            events.push(GameEvents::MainMenuReturn);
+        }
+        Ok(())
+    } else {
+        Err(())
+    }
+}
+
+pub fn draw(game_data: &GameState) -> Result<(), ()> {
+    if let ActiveState::Summary(s_data) = &game_data.active_state {
+        let strings = &game_data.strings;
+        let (backdrop, title, title_color) = if s_data.won {
+            ("win_screen", "summary.won", GREEN)
+        } else {
+            ("lose_screen", "summary.lost", RED)
+        };
+        draw_texture(game_data.textures.get(backdrop).unwrap(), 0.0, 0.0, WHITE);
+        draw_rectangle(0.0, 0.0, 960.0, 600.0, Color::new(0.0, 0.0, 0.0, 0.75));
+        draw_text_centered(
+            strings.get(title),
+            Vec2::new(480.0, 50.0),
+            64.0,
+            title_color,
+        );
+
+        let (columns, cell) = grid_layout(s_data.history.len());
+        for (i, result) in s_data.history.iter().enumerate() {
+            let pop = Tween::new(0.0, 1.0, CELL_POP_TIME, Ease::OutBack)
+                .with_delay(DOORS_TIME + CELL_STAGGER * i as f32)
+                .sample(s_data.time);
+            if pop <= 0.0 {
+                continue;
+            }
+            let corner = Vec2::new(
+                GRID.x + cell.x * (i % columns) as f32,
+                GRID.y + cell.y * (i / columns) as f32,
+            );
+            draw_cell(game_data, result, corner, cell, pop);
+        }
+
+        s_data
+            .score
+            .draw(strings, SCORE_X, s_data.time - s_data.score_start());
+        if let Some(daily) = &s_data.daily {
+            daily.draw(strings);
+        }
+
+        RETRY_BUTTON.draw(strings.get("summary.retry"), &s_data.mouse_pos);
+        NEW_RUN_BUTTON.draw(strings.get("summary.new_run"), &s_data.mouse_pos);
+        MENU_BUTTON.draw(strings.get("summary.menu"), &s_data.mouse_pos);
+
+        if s_data.time < DOORS_TIME {
+            draw_doors(
+                &game_data.textures,
+                doors_opening(DOORS_TIME).sample(s_data.time),
+            );
+        }
+        Ok(())
+    } else {
+        Err(())
+    }
+}
+
+/// One microgame's plate, scaled by `pop` as it comes in, with how it went underneath
+fn draw_cell(game_data: &GameState, result: &MicrogameResult, corner: Vec2, cell: Vec2, pop: f32) {
+    let plate_size = Vec2::new(cell.x - 8.0, (cell.x - 8.0) * 0.75) * pop;
+    let center = corner + Vec2::new(cell.x / 2.0, (cell.x - 8.0) * 0.375 + 4.0);
+    let top_left = center - plate_size / 2.0;
+    let tint = if result.won_at.is_some() {
+        WHITE
+    } else {
+        Color::new(0.6, 0.3, 0.3, 1.0)
+    };
+    match game_data.textures.get(plate_key_of(result.name)) {
+        Some(plate) => draw_texture_ex(
+            plate,
+            top_left.x,
+            top_left.y,
+            tint,
+            DrawTextureParams {
+                dest_size: Some(plate_size),
+                ..Default::default()
+            },
+        ),
+        None => draw_rectangle(
+            top_left.x,
+            top_left.y,
+            plate_size.x,
+            plate_size.y,
+            Color::new(0.2, 0.2, 0.25, 1.0),
+        ),
+    }
+    if result.boss {
+        draw_rectangle_lines(top_left.x, top_left.y, plate_size.x, plate_size.y, 4.0, RED);
+    }
+
+    let (text, color) = match result.won_at {
+        Some(t) => (format!("{t:.2}s"), GREEN),
+        None => ("X".to_string(), RED),
+    };
+    draw_text_centered(
+        &text,
+        Vec2::new(center.x, corner.y + cell.y - LABEL_HEIGHT / 2.0),
+        (LABEL_HEIGHT - 4.0) * pop,
+        color,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_fits_every_run_length() {
+        for mode in GameMode::ALL {
+            // Every stage plus a loss on each life
+            let config = mode.config();
+            let count = (config.length + config.lives) as usize;
+            let (columns, cell) = grid_layout(count);
+            assert!(count.div_ceil(columns) as f32 * cell.y <= GRID.h);
+            assert!(columns as f32 * cell.x <= GRID.w + 0.01);
+        }
+        assert_eq!(grid_layout(15).0, 5);
+    }
+}
@@ -0,0 +1,277 @@
+use macroquad::prelude::*;
+
+/// Values that can be blended between two endpoints
+pub trait Lerp: Copy {
+    fn lerp(self, to: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+}
+
+impl Lerp for Vec2 {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+}
+
+impl Lerp for Color {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        Color::new(
+            Lerp::lerp(self.r, to.r, t),
+            Lerp::lerp(self.g, to.g, t),
+            Lerp::lerp(self.b, to.b, t),
+            Lerp::lerp(self.a, to.a, t),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Ease {
+    Linear,
+    InQuad,
+    OutQuad,
+    InOutQuad,
+    /// Fast at both ends and slow through the middle, good for things
+    /// that fly in, hang around to be read, then fly out
+    OutInQuad,
+    InCubic,
+    OutCubic,
+    InOutCubic,
+    /// Overshoots past 1.0 before settling
+    OutBack,
+}
+
+impl Ease {
+    pub const ALL: [Ease; 9] = [
+        Ease::Linear,
+        Ease::InQuad,
+        Ease::OutQuad,
+        Ease::InOutQuad,
+        Ease::OutInQuad,
+        Ease::InCubic,
+        Ease::OutCubic,
+        Ease::InOutCubic,
+        Ease::OutBack,
+    ];
+
+    /// Maps linear progress in 0..1 onto the curve, input is clamped
+    pub fn apply(self, t: f32) -> f32 {
+        let t = clamp(t, 0.0, 1.0);
+        match self {
+            Ease::Linear => t,
+            Ease::InQuad => t * t,
+            Ease::OutQuad => 1.0 - (1.0 - t) * (1.0 - t),
+            Ease::InOutQuad => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - 2.0 * (1.0 - t) * (1.0 - t)
+                }
+            }
+            Ease::OutInQuad => {
+                if t < 0.5 {
+                    0.5 * Ease::OutQuad.apply(t * 2.0)
+                } else {
+                    0.5 + 0.5 * Ease::InQuad.apply(t * 2.0 - 1.0)
+                }
+            }
+            Ease::InCubic => t * t * t,
+            Ease::OutCubic => 1.0 - (1.0 - t).powi(3),
+            Ease::InOutCubic => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - 4.0 * (1.0 - t).powi(3)
+                }
+            }
+            Ease::OutBack => {
+                const C1: f32 = 1.70158;
+                const C3: f32 = C1 + 1.0;
+                1.0 + C3 * (t - 1.0).powi(3) + C1 * (t - 1.0).powi(2)
+            }
+        }
+    }
+}
+
+/// A single eased move from one value to another
+#[derive(Debug, Clone, Copy)]
+pub struct Tween<T: Lerp> {
+    pub from: T,
+    pub to: T,
+    pub duration: f32,
+    pub delay: f32,
+    pub ease: Ease,
+}
+
+impl<T: Lerp> Tween<T> {
+    pub fn new(from: T, to: T, duration: f32, ease: Ease) -> Self {
+        Tween {
+            from,
+            to,
+            duration,
+            delay: 0.0,
+            ease,
+        }
+    }
+
+    pub fn with_delay(mut self, delay: f32) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    pub fn total_time(&self) -> f32 {
+        self.delay + self.duration
+    }
+
+    /// Linear progress through the tween at `elapsed` seconds, before easing
+    pub fn progress(&self, elapsed: f32) -> f32 {
+        if self.duration <= 0.0 {
+            return if elapsed >= self.delay { 1.0 } else { 0.0 };
+        }
+        clamp((elapsed - self.delay) / self.duration, 0.0, 1.0)
+    }
+
+    pub fn sample(&self, elapsed: f32) -> T {
+        self.from
+            .lerp(self.to, self.ease.apply(self.progress(elapsed)))
+    }
+
+    pub fn is_finished(&self, elapsed: f32) -> bool {
+        elapsed >= self.total_time()
+    }
+}
+
+/// Tweens played back to back, each picking up where the previous one ends
+#[derive(Debug, Clone)]
+pub struct Sequence<T: Lerp> {
+    steps: Vec<Tween<T>>,
+}
+
+impl<T: Lerp> Sequence<T> {
+    pub fn new(start: T) -> Self {
+        Sequence {
+            steps: vec![Tween::new(start, start, 0.0, Ease::Linear)],
+        }
+    }
+
+    /// Appends a move from the current end value to `to`
+    pub fn then(mut self, to: T, duration: f32, ease: Ease) -> Self {
+        let from = self.end_value();
+        self.steps.push(Tween::new(from, to, duration, ease));
+        self
+    }
+
+    /// Holds the current end value for `duration` seconds
+    pub fn wait(self, duration: f32) -> Self {
+        let hold = self.end_value();
+        self.then(hold, duration, Ease::Linear)
+    }
+
+    pub fn end_value(&self) -> T {
+        self.steps.last().unwrap().to
+    }
+
+    pub fn total_time(&self) -> f32 {
+        self.steps.iter().map(|s| s.total_time()).sum()
+    }
+
+    pub fn sample(&self, elapsed: f32) -> T {
+        let mut remaining = elapsed;
+        for step in self.steps.iter() {
+            if remaining < step.total_time() {
+                return step.sample(remaining);
+            }
+            remaining -= step.total_time();
+        }
+        self.end_value()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn curves_hit_endpoints() {
+        for ease in Ease::ALL {
+            assert!(ease.apply(0.0).abs() < 1e-5, "{ease:?} at 0");
+            assert!((ease.apply(1.0) - 1.0).abs() < 1e-5, "{ease:?} at 1");
+        }
+    }
+
+    #[test]
+    fn curves_clamp_input() {
+        for ease in Ease::ALL {
+            assert_eq!(ease.apply(-3.0), ease.apply(0.0));
+            assert_eq!(ease.apply(7.0), ease.apply(1.0));
+        }
+    }
+
+    #[test]
+    fn curves_are_monotonic() {
+        for ease in Ease::ALL {
+            if ease == Ease::OutBack {
+                continue;
+            }
+            let mut last = ease.apply(0.0);
+            for i in 1..=1000 {
+                let v = ease.apply(i as f32 / 1000.0);
+                assert!(v >= last, "{ease:?} decreased at step {i}");
+                last = v;
+            }
+        }
+    }
+
+    #[test]
+    fn out_back_overshoots() {
+        let peak = (0..=100)
+            .map(|i| Ease::OutBack.apply(i as f32 / 100.0))
+            .fold(0.0, f32::max);
+        assert!(peak > 1.0);
+    }
+
+    #[test]
+    fn out_in_quad_lingers_in_middle() {
+        let d = 0.01;
+        let middle = Ease::OutInQuad.apply(0.5 + d) - Ease::OutInQuad.apply(0.5);
+        let start = Ease::OutInQuad.apply(d) - Ease::OutInQuad.apply(0.0);
+        assert!((Ease::OutInQuad.apply(0.5) - 0.5).abs() < 1e-5);
+        assert!(middle < start);
+    }
+
+    #[test]
+    fn tween_respects_delay() {
+        let t = Tween::new(0.0, 10.0, 1.0, Ease::Linear).with_delay(0.5);
+        assert_eq!(t.sample(0.0), 0.0);
+        assert_eq!(t.sample(0.5), 0.0);
+        assert!((t.sample(1.0) - 5.0).abs() < 1e-5);
+        assert_eq!(t.sample(2.0), 10.0);
+        assert!(!t.is_finished(1.4));
+        assert!(t.is_finished(1.5));
+    }
+
+    #[test]
+    fn tween_lerps_vectors_and_colors() {
+        let v = Tween::new(Vec2::ZERO, Vec2::new(4.0, -2.0), 1.0, Ease::Linear);
+        assert_eq!(v.sample(0.5), Vec2::new(2.0, -1.0));
+
+        let c = Tween::new(BLACK, WHITE, 2.0, Ease::Linear).sample(1.0);
+        assert!((c.r - 0.5).abs() < 1e-5 && (c.a - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn sequence_chains_steps() {
+        let s = Sequence::new(0.0)
+            .then(10.0, 1.0, Ease::Linear)
+            .wait(1.0)
+            .then(0.0, 2.0, Ease::Linear);
+        assert_eq!(s.total_time(), 4.0);
+        assert!((s.sample(0.5) - 5.0).abs() < 1e-5);
+        assert_eq!(s.sample(1.5), 10.0);
+        assert!((s.sample(3.0) - 5.0).abs() < 1e-5);
+        assert_eq!(s.sample(10.0), 0.0);
+    }
+}
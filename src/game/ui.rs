@@ -0,0 +1,52 @@
+use macroquad::prelude::*;
+
+use super::{vec2_in_range, FrameInput, MousePressState};
+
+/// Plain text button used by the menus, drawn as a box with a centered label
+pub struct Button {
+    pub range: (Vec2, Vec2),
+}
+
+impl Button {
+    pub const fn new(x: f32, y: f32, w: f32, h: f32) -> Self {
+        Button {
+            range: (Vec2::new(x, y), Vec2::new(x + w, y + h)),
+        }
+    }
+
+    pub fn hovered(&self, mouse_pos: &Vec2) -> bool {
+        vec2_in_range(mouse_pos, &self.range)
+    }
+
+    pub fn clicked(&self, input: &FrameInput) -> bool {
+        input.mouse_state == MousePressState::JustPressed && self.hovered(&input.mouse_position)
+    }
+
+    pub fn draw(&self, label: &str, mouse_pos: &Vec2) {
+        let (min, max) = self.range;
+        let size = max - min;
+        let (fill, line) = if self.hovered(mouse_pos) {
+            (Color::new(0.15, 0.45, 0.2, 0.9), GREEN)
+        } else {
+            (
+                Color::new(0.1, 0.1, 0.12, 0.85),
+                Color::new(0.8, 0.25, 0.25, 1.0),
+            )
+        };
+        draw_rectangle(min.x, min.y, size.x, size.y, fill);
+        draw_rectangle_lines(min.x, min.y, size.x, size.y, 3.0, line);
+        draw_text_centered(label, (min + max) / 2.0, 24.0, WHITE);
+    }
+}
+
+/// Draws text with its visual center on `center`
+pub fn draw_text_centered(text: &str, center: Vec2, font_size: f32, color: Color) {
+    let dims = measure_text(text, None, font_size as u16, 1.0);
+    draw_text(
+        text,
+        center.x - dims.width / 2.0,
+        center.y + dims.offset_y / 2.0,
+        font_size,
+        color,
+    );
+}
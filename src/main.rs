@@ -1,16 +1,9 @@
-use game::{
+use macroquad::{prelude::*, rand, window::Conf};
+use microship::game::{
     draw_game_state, gather_frame_input, get_texture_images, init_game_state, load_song,
-    update_game_state, GameState, ThreadLoadResource,
+    update_game_state, GameState,
 };
-use macroquad::{
-    audio::{load_sound_from_bytes, Sound},
-    prelude::*,
-    rand, time,
-    window::Conf,
-};
-use std::{collections::HashMap, sync::mpsc, thread, time::SystemTime};
-
-mod game;
+use std::{sync::mpsc, thread, time::SystemTime};
 
 fn game_config() -> Conf {
     Conf {
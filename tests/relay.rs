@@ -0,0 +1,150 @@
+use std::{
+    io::Write,
+    net::{TcpListener, TcpStream},
+    thread,
+    time::{Duration, Instant},
+};
+
+use microship::game::{
+    net::{run_relay, Client, Connecting, Message},
+    run_config::GameMode,
+};
+
+/// Relay on a free localhost port, running until the test process ends
+fn start_relay() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    thread::spawn(move || run_relay(listener));
+    addr
+}
+
+/// Polls until the next message turns up
+fn next_message(client: &mut Client) -> Message {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    loop {
+        if let Some(message) = client.poll().unwrap().into_iter().next() {
+            return message;
+        }
+        assert!(Instant::now() < deadline, "relay went quiet");
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+/// Host and guest, both already told the run has started
+fn paired(addr: &str, mode: GameMode, seed: u64) -> (Client, Client) {
+    let mut host = Client::connect(addr).unwrap();
+    host.send(&Message::Host { mode, seed }).unwrap();
+    let Message::Room { room } = next_message(&mut host) else {
+        panic!("expected a room code");
+    };
+
+    let mut guest = Client::connect(addr).unwrap();
+    guest.send(&Message::Join { room }).unwrap();
+    let start = Message::Start { mode, seed };
+    assert_eq!(next_message(&mut host), start);
+    assert_eq!(next_message(&mut guest), start);
+    (host, guest)
+}
+
+#[test]
+fn results_reach_the_opponent() {
+    let addr = start_relay();
+    let (mut host, mut guest) = paired(&addr, GameMode::Short, 42);
+
+    let result = Message::Result {
+        stage: 0,
+        won: true,
+        time_ms: 1250,
+        lives: 3,
+    };
+    host.send(&result).unwrap();
+    assert_eq!(next_message(&mut guest), result);
+    guest.send(&Message::Done).unwrap();
+    assert_eq!(next_message(&mut host), Message::Done);
+}
+
+#[test]
+fn unknown_rooms_are_refused() {
+    let addr = start_relay();
+    let mut guest = Client::connect(&addr).unwrap();
+    guest
+        .send(&Message::Join {
+            room: "ZZZZ".to_string(),
+        })
+        .unwrap();
+    assert!(matches!(next_message(&mut guest), Message::Error { .. }));
+}
+
+#[test]
+fn leaving_tells_the_opponent() {
+    let addr = start_relay();
+    let (host, mut guest) = paired(&addr, GameMode::OneLife, 7);
+    drop(host);
+    assert_eq!(next_message(&mut guest), Message::Left);
+}
+
+#[test]
+fn connecting_runs_off_the_callers_thread() {
+    let addr = start_relay();
+    let connecting = Connecting::start(addr);
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let mut client = loop {
+        if let Some(result) = connecting.poll() {
+            break result.unwrap();
+        }
+        assert!(Instant::now() < deadline, "never connected");
+        thread::sleep(Duration::from_millis(5));
+    };
+    client
+        .send(&Message::Join {
+            room: "ZZZZ".to_string(),
+        })
+        .unwrap();
+    assert!(matches!(next_message(&mut client), Message::Error { .. }));
+
+    // Nothing listening, it fails without the caller ever waiting on it
+    let closed = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = closed.local_addr().unwrap().to_string();
+    drop(closed);
+    let connecting = Connecting::start(addr);
+    let failed = loop {
+        if let Some(result) = connecting.poll() {
+            break result;
+        }
+        thread::sleep(Duration::from_millis(5));
+    };
+    assert!(failed.is_err());
+}
+
+#[test]
+fn a_stalled_client_doesnt_hold_up_other_rooms() {
+    let addr = start_relay();
+    let mut host = Client::connect(&addr).unwrap();
+    host.send(&Message::Host {
+        mode: GameMode::Short,
+        seed: 1,
+    })
+    .unwrap();
+    let Message::Room { room } = next_message(&mut host) else {
+        panic!("expected a room code");
+    };
+    // Joins and then never reads a thing
+    let mut stalled = TcpStream::connect(&addr).unwrap();
+    writeln!(stalled, "{}", Message::Join { room }.encode()).unwrap();
+    next_message(&mut host);
+
+    // Far more than the socket buffers hold, so the relay ends up stuck writing
+    let result = Message::Result {
+        stage: 0,
+        won: true,
+        time_ms: 1000,
+        lives: 3,
+    };
+    for _ in 0..2_000_000 {
+        let _ = host.send(&result);
+    }
+
+    let (mut other_host, mut other_guest) = paired(&addr, GameMode::Standard, 2);
+    other_host.send(&Message::Done).unwrap();
+    assert_eq!(next_message(&mut other_guest), Message::Done);
+}
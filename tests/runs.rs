@@ -0,0 +1,56 @@
+use microship::game::{bot::BotSkill, run_config::GameMode, sim::simulate_run};
+
+const FRAME: f32 = 1.0 / 60.0;
+
+const SLOPPY: BotSkill = BotSkill {
+    cursor_speed: 1200.0,
+    reaction_delay: 0.3,
+    miss_rate: 0.2,
+};
+
+#[test]
+fn same_seed_plays_out_the_same() {
+    for seed in [0, 1, 99, u64::MAX] {
+        assert_eq!(
+            simulate_run(GameMode::Standard, seed, SLOPPY, FRAME),
+            simulate_run(GameMode::Standard, seed, SLOPPY, FRAME)
+        );
+    }
+}
+
+#[test]
+fn different_seeds_shuffle_the_queue() {
+    let names = |seed| {
+        simulate_run(GameMode::Standard, seed, BotSkill::PERFECT, FRAME)
+            .results
+            .iter()
+            .map(|r| r.name)
+            .collect::<Vec<_>>()
+    };
+    assert_ne!(names(1), names(2));
+}
+
+#[test]
+fn runs_end_when_lives_or_stages_run_out() {
+    for mode in GameMode::ALL {
+        let config = mode.config();
+        for seed in 0..10 {
+            let report = simulate_run(mode, seed, SLOPPY, FRAME);
+            let losses = report.results.iter().filter(|r| r.won_at.is_none()).count();
+            // Beaten bosses can hand out extra lives
+            let bonus = report
+                .results
+                .iter()
+                .filter(|r| r.boss && r.won_at.is_some())
+                .count();
+            let lives = config.lives as usize;
+            if report.won {
+                assert_eq!(report.results.len(), config.length as usize);
+                assert!(losses < lives + bonus);
+            } else {
+                assert!(losses >= lives && losses <= lives + bonus);
+                assert!(report.results.last().unwrap().won_at.is_none());
+            }
+        }
+    }
+}